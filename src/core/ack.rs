@@ -0,0 +1,44 @@
+use crate::errors::{EngineError, EngineResult};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::sync::oneshot;
+
+/// Outcome of a submitted transaction, resolves once the owning worker has applied it.
+/// Transactions refused by the engine itself resolve immediately.
+pub struct Acknowledgement(State);
+
+enum State {
+    Ready(Option<EngineResult<()>>),
+    Pending(oneshot::Receiver<EngineResult<()>>, EngineError),
+}
+
+impl Acknowledgement {
+    pub(crate) fn ready(result: EngineResult<()>) -> Self {
+        Self(State::Ready(Some(result)))
+    }
+
+    /// The failure is returned when the worker dies before answering.
+    pub(crate) fn pending(
+        response: oneshot::Receiver<EngineResult<()>>,
+        failure: EngineError,
+    ) -> Self {
+        Self(State::Pending(response, failure))
+    }
+}
+
+impl Future for Acknowledgement {
+    type Output = EngineResult<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.get_mut().0 {
+            State::Ready(result) => {
+                Poll::Ready(result.take().unwrap_or(Err(EngineError::InternalError())))
+            }
+            State::Pending(response, failure) => Pin::new(response).poll(cx).map(|result| {
+                result.unwrap_or_else(|_| {
+                    Err(std::mem::replace(failure, EngineError::InternalError()))
+                })
+            }),
+        }
+    }
+}
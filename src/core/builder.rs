@@ -0,0 +1,223 @@
+use crate::core::engine::PaymentEngine;
+use crate::core::fees::FeeSchedule;
+use crate::core::history::{DiskStore, HistoryConfig};
+use crate::core::policy::{DisputeWindow, DuplicatePolicy, WalletPolicy};
+use crate::core::supervisor::Supervision;
+use crate::core::wal::WalSync;
+use crate::errors::{EngineError, EngineResult};
+use crate::model::account::AMOUNT_SCALE;
+use crate::model::client::ClientId;
+use serde::Deserialize;
+use std::fs;
+use std::sync::Arc;
+
+pub const DEFAULT_WORKERS_SIZE: usize = 10;
+pub const DEFAULT_BUFFER_SIZE: usize = 100;
+
+/// Engine options, e.g. read from a toml file. Missing options keep their defaults.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineConfig {
+    /// Number of workers, clients are spread over them by `client_id % workers`.
+    pub workers: usize,
+    /// Capacity of the channel of every worker.
+    pub buffer: usize,
+    /// Maximal number of decimal places of an amount.
+    pub precision: u32,
+    pub allow_redispute: bool,
+    pub dispute_window: Option<DisputeWindow>,
+    pub duplicates: DuplicatePolicy,
+    pub supervision: Supervision,
+    /// Client credited with every fee, required once any fee is configured.
+    pub house_account: Option<ClientId>,
+    pub fees: FeeSchedule,
+    pub history: HistoryConfig,
+    pub wal_sync: WalSync,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        let policy = WalletPolicy::default();
+
+        Self {
+            workers: DEFAULT_WORKERS_SIZE,
+            buffer: DEFAULT_BUFFER_SIZE,
+            precision: policy.precision,
+            allow_redispute: policy.allow_redispute,
+            dispute_window: policy.dispute_window,
+            duplicates: DuplicatePolicy::default(),
+            supervision: Supervision::default(),
+            house_account: None,
+            fees: FeeSchedule::default(),
+            history: HistoryConfig::default(),
+            wal_sync: WalSync::default(),
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn load(path: &str) -> EngineResult<EngineConfig> {
+        let content = fs::read_to_string(path)?;
+
+        toml::from_str(&content).map_err(|error| EngineError::InvalidConfig(error.to_string()))
+    }
+}
+
+/// Validates the options before the engine is created, nothing is truncated or clamped.
+#[derive(Debug, Clone, Default)]
+pub struct PaymentEngineBuilder {
+    config: EngineConfig,
+}
+
+impl PaymentEngineBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: EngineConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.config.workers = workers;
+        self
+    }
+
+    pub fn buffer(mut self, buffer: usize) -> Self {
+        self.config.buffer = buffer;
+        self
+    }
+
+    pub fn precision(mut self, precision: u32) -> Self {
+        self.config.precision = precision;
+        self
+    }
+
+    pub fn allow_redispute(mut self, allow_redispute: bool) -> Self {
+        self.config.allow_redispute = allow_redispute;
+        self
+    }
+
+    pub fn dispute_window(mut self, window: DisputeWindow) -> Self {
+        self.config.dispute_window = Some(window);
+        self
+    }
+
+    pub fn duplicates(mut self, duplicates: DuplicatePolicy) -> Self {
+        self.config.duplicates = duplicates;
+        self
+    }
+
+    pub fn supervision(mut self, supervision: Supervision) -> Self {
+        self.config.supervision = supervision;
+        self
+    }
+
+    pub fn house_account(mut self, client: ClientId) -> Self {
+        self.config.house_account = Some(client);
+        self
+    }
+
+    pub fn fees(mut self, fees: FeeSchedule) -> Self {
+        self.config.fees = fees;
+        self
+    }
+
+    pub fn history(mut self, history: HistoryConfig) -> Self {
+        self.config.history = history;
+        self
+    }
+
+    pub fn wal_sync(mut self, sync: WalSync) -> Self {
+        self.config.wal_sync = sync;
+        self
+    }
+
+    pub fn build(self) -> EngineResult<PaymentEngine> {
+        let config = self.config;
+
+        let workers = u16::try_from(config.workers)
+            .ok()
+            .filter(|workers| *workers > 0)
+            .ok_or_else(|| {
+                EngineError::InvalidConfig(format!(
+                    "workers has to be between 1 and {}, got {}",
+                    u16::MAX,
+                    config.workers
+                ))
+            })?;
+
+        if config.buffer == 0 {
+            return Err(EngineError::InvalidConfig(
+                "buffer has to be at least 1".to_string(),
+            ));
+        }
+
+        // Reports are written with a fixed scale, a higher precision would be rounded there.
+        if config.precision > AMOUNT_SCALE {
+            return Err(EngineError::InvalidConfig(format!(
+                "precision has to be at most {}, got {}",
+                AMOUNT_SCALE, config.precision
+            )));
+        }
+
+        if let Some(DisputeWindow::Seconds(0) | DisputeWindow::Transactions(0)) =
+            config.dispute_window
+        {
+            return Err(EngineError::InvalidConfig(
+                "dispute_window has to be greater than 0".to_string(),
+            ));
+        }
+
+        if config.wal_sync == WalSync::Batch(0) {
+            return Err(EngineError::InvalidConfig(
+                "wal_sync batch has to be greater than 0".to_string(),
+            ));
+        }
+
+        config.fees.validate().map_err(EngineError::InvalidConfig)?;
+
+        let house = match (config.house_account, config.fees.is_empty()) {
+            (None, false) => {
+                return Err(EngineError::InvalidConfig(
+                    "fees need a house_account to be credited to".to_string(),
+                ));
+            }
+            (house, _) => house,
+        };
+
+        if config.history != HistoryConfig::Memory && config.supervision == Supervision::Restart {
+            return Err(EngineError::InvalidConfig(
+                "history store cannot be combined with restart supervision".to_string(),
+            ));
+        }
+
+        let spill = match config.history {
+            HistoryConfig::Memory => None,
+            HistoryConfig::Disk { path } => Some((DiskStore::create(&path)?, 0)),
+            HistoryConfig::Spill { path, capacity } => Some((DiskStore::create(&path)?, capacity)),
+        };
+
+        let policy = WalletPolicy {
+            allow_redispute: config.allow_redispute,
+            precision: config.precision,
+            dispute_window: config.dispute_window,
+        };
+
+        let engine = PaymentEngine::configure(workers, config.buffer)
+            .with_policy(policy)
+            .with_duplicates(config.duplicates)
+            .with_supervision(config.supervision)
+            .with_wal_sync(config.wal_sync);
+
+        let engine = match spill {
+            Some((store, capacity)) => engine.with_history(Arc::new(store), capacity),
+            None => engine,
+        };
+
+        Ok(match house {
+            Some(house) => engine.with_fees(Arc::new(config.fees), house),
+            None => engine,
+        })
+    }
+}
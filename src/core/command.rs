@@ -0,0 +1,298 @@
+use crate::core::wallet::AccountWallet;
+use crate::errors::{EngineError, EngineResult};
+use crate::model::account::{Account, LockReason};
+use crate::model::asset::Asset;
+use crate::model::client::ClientId;
+use crate::model::rejection::Origin;
+use crate::model::statement::Step;
+use crate::model::trade::{Timestamp, Transaction, TransactionId};
+use rust_decimal::Decimal;
+use tokio::sync::oneshot;
+
+pub type Reply = oneshot::Sender<EngineResult<()>>;
+
+/// Transfer between two clients, the engine splits it into operations on both wallets.
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub client: ClientId,
+    pub to: ClientId,
+    pub trade: TransactionId,
+    pub asset: Asset,
+    pub amount: Decimal,
+}
+
+/// Transaction which changes the wallet of a single client, every kind except a transfer.
+#[derive(Debug, Clone)]
+pub enum WalletTransaction {
+    Deposit {
+        client: ClientId,
+        trade: TransactionId,
+        amount: Decimal,
+        asset: Asset,
+        timestamp: Option<Timestamp>,
+    },
+    Withdrawal {
+        client: ClientId,
+        trade: TransactionId,
+        amount: Decimal,
+        asset: Asset,
+        timestamp: Option<Timestamp>,
+    },
+    Dispute {
+        client: ClientId,
+        trade: TransactionId,
+        timestamp: Option<Timestamp>,
+    },
+    Resolve {
+        client: ClientId,
+        trade: TransactionId,
+    },
+    Chargeback {
+        client: ClientId,
+        trade: TransactionId,
+    },
+}
+
+impl WalletTransaction {
+    pub fn client_id(&self) -> ClientId {
+        match self {
+            WalletTransaction::Deposit { client, .. } => *client,
+            WalletTransaction::Withdrawal { client, .. } => *client,
+            WalletTransaction::Dispute { client, .. } => *client,
+            WalletTransaction::Resolve { client, .. } => *client,
+            WalletTransaction::Chargeback { client, .. } => *client,
+        }
+    }
+
+    pub fn trade_id(&self) -> TransactionId {
+        match self {
+            WalletTransaction::Deposit { trade, .. } => *trade,
+            WalletTransaction::Withdrawal { trade, .. } => *trade,
+            WalletTransaction::Dispute { trade, .. } => *trade,
+            WalletTransaction::Resolve { trade, .. } => *trade,
+            WalletTransaction::Chargeback { trade, .. } => *trade,
+        }
+    }
+
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            WalletTransaction::Deposit { timestamp, .. } => *timestamp,
+            WalletTransaction::Withdrawal { timestamp, .. } => *timestamp,
+            WalletTransaction::Dispute { timestamp, .. } => *timestamp,
+            _ => None,
+        }
+    }
+}
+
+/// Transfers are split off, they cannot be applied to a single wallet.
+impl TryFrom<Transaction> for WalletTransaction {
+    type Error = Transfer;
+
+    fn try_from(tx: Transaction) -> Result<Self, Self::Error> {
+        Ok(match tx {
+            Transaction::Deposit {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            } => WalletTransaction::Deposit {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            },
+            Transaction::Withdrawal {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            } => WalletTransaction::Withdrawal {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            },
+            Transaction::Transfer {
+                client,
+                to,
+                trade,
+                amount,
+                asset,
+            } => {
+                return Err(Transfer {
+                    client,
+                    to,
+                    trade,
+                    asset,
+                    amount,
+                });
+            }
+            Transaction::Dispute {
+                client,
+                trade,
+                timestamp,
+            } => WalletTransaction::Dispute {
+                client,
+                trade,
+                timestamp,
+            },
+            Transaction::Resolve { client, trade } => WalletTransaction::Resolve { client, trade },
+            Transaction::Chargeback { client, trade } => {
+                WalletTransaction::Chargeback { client, trade }
+            }
+        })
+    }
+}
+
+impl From<WalletTransaction> for Transaction {
+    fn from(tx: WalletTransaction) -> Self {
+        match tx {
+            WalletTransaction::Deposit {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            } => Transaction::Deposit {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            },
+            WalletTransaction::Withdrawal {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            } => Transaction::Withdrawal {
+                client,
+                trade,
+                amount,
+                asset,
+                timestamp,
+            },
+            WalletTransaction::Dispute {
+                client,
+                trade,
+                timestamp,
+            } => Transaction::Dispute {
+                client,
+                trade,
+                timestamp,
+            },
+            WalletTransaction::Resolve { client, trade } => Transaction::Resolve { client, trade },
+            WalletTransaction::Chargeback { client, trade } => {
+                Transaction::Chargeback { client, trade }
+            }
+        }
+    }
+}
+
+/// Single change applied by a worker to one of its wallets.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    Apply(WalletTransaction),
+    Reserve {
+        client: ClientId,
+        trade: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+        fee: Decimal,
+    },
+    Commit {
+        client: ClientId,
+        trade: TransactionId,
+    },
+    Release {
+        client: ClientId,
+        trade: TransactionId,
+    },
+    Credit {
+        client: ClientId,
+        trade: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+    },
+    /// Fee credited to the house account, applied even when the account is locked.
+    Collect {
+        client: ClientId,
+        trade: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+    },
+    Lock {
+        client: ClientId,
+        reason: LockReason,
+    },
+    Unlock {
+        client: ClientId,
+    },
+}
+
+impl Operation {
+    pub fn client_id(&self) -> ClientId {
+        match self {
+            Operation::Apply(tx) => tx.client_id(),
+            Operation::Reserve { client, .. } => *client,
+            Operation::Commit { client, .. } => *client,
+            Operation::Release { client, .. } => *client,
+            Operation::Credit { client, .. } => *client,
+            Operation::Collect { client, .. } => *client,
+            Operation::Lock { client, .. } => *client,
+            Operation::Unlock { client } => *client,
+        }
+    }
+
+    pub fn trade_id(&self) -> Option<TransactionId> {
+        match self {
+            Operation::Apply(tx) => Some(tx.trade_id()),
+            Operation::Reserve { trade, .. } => Some(*trade),
+            Operation::Commit { trade, .. } => Some(*trade),
+            Operation::Release { trade, .. } => Some(*trade),
+            Operation::Credit { trade, .. } => Some(*trade),
+            Operation::Collect { trade, .. } => Some(*trade),
+            Operation::Lock { .. } | Operation::Unlock { .. } => None,
+        }
+    }
+}
+
+pub enum Command {
+    /// Changes a wallet, the reply is present when the sender awaits the outcome,
+    /// otherwise a failure is reported as a rejection.
+    Execute {
+        operation: Operation,
+        origin: Option<Origin>,
+        reply: Option<Reply>,
+    },
+    /// Reads the balances of a single client in one asset, `None` when the client is unknown
+    /// or has no balance in the asset.
+    Account {
+        client: ClientId,
+        asset: Asset,
+        reply: oneshot::Sender<Option<Account>>,
+    },
+    /// Adds a transaction rejected by the engine to the statement of the client,
+    /// with the balances at that point.
+    Rejected {
+        client: ClientId,
+        step: Step,
+        origin: Option<Origin>,
+        error: EngineError,
+    },
+    /// Reads the balances of every client owned by the worker.
+    Balances {
+        reply: oneshot::Sender<Vec<Account>>,
+    },
+    /// Answers once every command queued before it has been handled.
+    Sync { reply: oneshot::Sender<()> },
+    /// Copies all wallets owned by the worker.
+    Snapshot {
+        reply: oneshot::Sender<Vec<AccountWallet>>,
+    },
+}
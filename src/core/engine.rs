@@ -1,75 +1,1140 @@
+use crate::core::ack::Acknowledgement;
+use crate::core::builder::{DEFAULT_BUFFER_SIZE, DEFAULT_WORKERS_SIZE, PaymentEngineBuilder};
+use crate::core::command::{Command, Operation, Reply, Transfer, WalletTransaction};
+use crate::core::fees::{self, FeeCharge, FeeKind, FeePolicy, NoFees};
+use crate::core::history::{Spill, TradeStore};
+use crate::core::policy::{DuplicatePolicy, WalletPolicy};
+use crate::core::query::{EngineQueries, Routes};
+use crate::core::registry::TransactionRegistry;
+use crate::core::snapshot::EngineSnapshot;
+use crate::core::supervisor::{InFlight, Journal, Supervision};
+use crate::core::transfer::{PendingTransfer, Phase};
+use crate::core::wal::{LogEntry, WalSync, WriteAheadLog};
 use crate::core::wallet::AccountWallet;
 use crate::core::worker::EngineWorker;
-use crate::errors::{EngineError, EngineResult};
-use crate::model::account::Account;
+use crate::errors::{EngineError, EngineResult, ErrorCategory};
+use crate::model::account::{Account, LockReason};
+use crate::model::admin::{AdminAction, AdminCommand, AuditEntry};
+use crate::model::asset::Asset;
 use crate::model::client::ClientId;
+use crate::model::ledger::JournalEntry;
+use crate::model::rejection::{Origin, Rejection};
 use crate::model::report::Report;
-use crate::model::trade::Transaction;
-use std::collections::HashMap;
-use tokio::sync::mpsc;
-use tokio::task::JoinHandle;
-use tracing::{info, warn};
-
-const DEFAULT_WORKERS_SIZE: usize = 10;
-const DEFAULT_BUFFER_SIZE: usize = 100;
+use crate::model::statement::{StatementLine, Step};
+use crate::model::trade::{Timestamp, Transaction, TransactionId};
+use rust_decimal::Decimal;
+use std::any::Any;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc::error::SendError;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::{self, JoinError, JoinHandle};
+use tracing::{error, info, warn};
 
 type Wallets = HashMap<ClientId, AccountWallet>;
+type Rejections = mpsc::UnboundedSender<Rejection>;
+type Charges = mpsc::UnboundedSender<FeeCharge>;
+type Statements = mpsc::UnboundedSender<StatementLine>;
+type Audits = mpsc::UnboundedSender<AuditEntry>;
+type Entries = mpsc::UnboundedSender<JournalEntry>;
+
+/// Client credited with every fee, workers report the fees they charged over the channel.
+struct HouseAccount {
+    client: ClientId,
+    sender: Charges,
+    charges: mpsc::UnboundedReceiver<FeeCharge>,
+}
+
+/// Channel of the rejections, muted while the write-ahead log is replayed so entries
+/// rejected before the restart are not reported again.
+#[derive(Clone)]
+struct RejectionSink {
+    sender: Rejections,
+    muted: Arc<AtomicBool>,
+}
+
+impl RejectionSink {
+    fn send(&self, rejection: Rejection) {
+        if self.muted.load(Ordering::Acquire) {
+            return;
+        }
+
+        self.sender.send(rejection).unwrap_or_else(|_| {
+            warn!("Rejection has not been reported");
+        });
+    }
+}
+
+/// Client whose every step is sent over the channel, in the order it was handled.
+#[derive(Clone)]
+struct Statement {
+    client: ClientId,
+    sender: Statements,
+}
 
 pub struct PaymentEngine {
     workers_size: u16,
     worker_buffer: usize,
-    workers: HashMap<usize, (mpsc::Sender<Transaction>, JoinHandle<Wallets>)>,
+    policy: WalletPolicy,
+    duplicates: DuplicatePolicy,
+    fees: Arc<dyn FeePolicy>,
+    house: Option<HouseAccount>,
+    spill: Option<Spill>,
+    registry: TransactionRegistry,
+    transfers: Vec<PendingTransfer>,
+    audit: Vec<AuditEntry>,
+    audits: Option<Audits>,
+    wal: Option<WriteAheadLog>,
+    wal_sync: WalSync,
+    replaying: bool,
+    wal_sequence: u64,
+    rejections: Option<RejectionSink>,
+    statement: Option<Statement>,
+    entries: Option<Entries>,
+    supervision: Supervision,
+    journals: HashMap<usize, Journal>,
+    in_flight: HashMap<usize, InFlight>,
+    failed: HashSet<usize>,
+    workers: HashMap<usize, (mpsc::Sender<Command>, JoinHandle<Wallets>)>,
+    routes: Arc<RwLock<Routes>>,
 }
 
 impl Default for PaymentEngine {
     fn default() -> Self {
-        Self::new(DEFAULT_WORKERS_SIZE)
+        Self::configure(DEFAULT_WORKERS_SIZE as u16, DEFAULT_BUFFER_SIZE)
     }
 }
 
 impl PaymentEngine {
+    /// Options are validated by the builder, `new` only takes the pool size.
+    pub fn builder() -> PaymentEngineBuilder {
+        PaymentEngineBuilder::new()
+    }
+
+    /// Engine with default options. The pool size is clamped to `1..=u16::MAX`,
+    /// `builder().workers(..).build()` rejects an invalid one instead.
     pub fn new(pool_size: usize) -> PaymentEngine {
+        Self::configure(
+            pool_size.clamp(1, u16::MAX as usize) as u16,
+            DEFAULT_BUFFER_SIZE,
+        )
+    }
+
+    pub(crate) fn configure(pool_size: u16, buffer: usize) -> PaymentEngine {
         Self {
-            workers_size: pool_size as u16,
-            worker_buffer: DEFAULT_BUFFER_SIZE,
-            workers: HashMap::with_capacity(pool_size),
+            workers_size: pool_size,
+            worker_buffer: buffer,
+            policy: WalletPolicy::default(),
+            duplicates: DuplicatePolicy::default(),
+            fees: Arc::new(NoFees),
+            house: None,
+            spill: None,
+            registry: TransactionRegistry::new(),
+            transfers: vec![],
+            audit: vec![],
+            audits: None,
+            wal: None,
+            wal_sync: WalSync::default(),
+            replaying: false,
+            wal_sequence: 0,
+            rejections: None,
+            statement: None,
+            entries: None,
+            supervision: Supervision::default(),
+            journals: HashMap::new(),
+            in_flight: HashMap::new(),
+            failed: HashSet::new(),
+            workers: HashMap::with_capacity(pool_size as usize),
+            routes: Arc::new(RwLock::new(Routes::default())),
         }
     }
 
-    pub async fn report(mut self) -> Result<Report, EngineError> {
-        let handlers: Vec<_> = self
-            .workers
-            .drain()
-            .map(|(_, (_, handler))| handler)
-            .collect();
+    pub fn with_policy(mut self, policy: WalletPolicy) -> PaymentEngine {
+        self.policy = policy;
+        self
+    }
+
+    pub fn with_duplicates(mut self, duplicates: DuplicatePolicy) -> PaymentEngine {
+        self.duplicates = duplicates;
+        self
+    }
+
+    /// Fees are debited from the wallets as separate entries and credited to the house account.
+    /// Should be called before any transaction is processed.
+    pub fn with_fees(mut self, fees: Arc<dyn FeePolicy>, house: ClientId) -> PaymentEngine {
+        let (sender, charges) = mpsc::unbounded_channel();
+
+        self.fees = fees;
+        self.house = Some(HouseAccount {
+            client: house,
+            sender,
+            charges,
+        });
+        self
+    }
+
+    /// Keeps at most `capacity` deposits and withdrawals of every client in memory, the least
+    /// recently used ones are paged out to the store and read back when they are disputed.
+    /// Should be called before any transaction is processed. Workers restarted by
+    /// `Supervision::Restart` would share the store with the state they are rebuilt from,
+    /// so the two should not be combined.
+    pub fn with_history(mut self, store: Arc<dyn TradeStore>, capacity: usize) -> PaymentEngine {
+        self.spill = Some(Spill { store, capacity });
+        self
+    }
+
+    /// Every rejected transaction, including the ones rejected later by workers, is sent to the channel.
+    pub fn with_rejections(mut self, rejections: Rejections) -> PaymentEngine {
+        self.rejections = Some(RejectionSink {
+            sender: rejections,
+            muted: Arc::new(AtomicBool::new(false)),
+        });
+        self
+    }
+
+    /// Every audit entry is sent to the channel as soon as the admin command is applied.
+    pub fn with_audit(mut self, audits: Audits) -> PaymentEngine {
+        self.audits = Some(audits);
+        self
+    }
+
+    /// Every ledger entry is sent to the channel once posted, in posting order per client.
+    /// Wallets keep only their balances. Should be called before any transaction is processed.
+    pub fn with_journal(mut self, entries: Entries) -> PaymentEngine {
+        self.entries = Some(entries);
+        self
+    }
+
+    /// Every transaction applied to or rejected for the client is sent to the channel,
+    /// with the balances after it. Should be called before any transaction is processed.
+    pub fn with_statement(mut self, client: ClientId, statement: Statements) -> PaymentEngine {
+        self.statement = Some(Statement {
+            client,
+            sender: statement,
+        });
+        self
+    }
+
+    /// With `Supervision::Restart` every worker keeps a journal of the operations applied
+    /// since the last restore or checkpoint, so it can be rebuilt after a failure.
+    /// How often `recover` syncs the write-ahead log, every record by default.
+    pub fn with_wal_sync(mut self, sync: WalSync) -> PaymentEngine {
+        self.wal_sync = sync;
+        self
+    }
+
+    pub fn with_supervision(mut self, supervision: Supervision) -> PaymentEngine {
+        self.supervision = supervision;
+        self
+    }
+
+    /// Continues from a previously saved state, wallets are spread over the current workers pool.
+    /// Should be called once the engine is configured, and fails if it has already processed
+    /// transactions, as their wallets would be replaced.
+    pub fn restore(mut self, snapshot: EngineSnapshot) -> EngineResult<PaymentEngine> {
+        if !self.workers.is_empty() || !self.failed.is_empty() {
+            return Err(EngineError::InvalidConfig(String::from(
+                "snapshot has to be restored before any transaction is processed",
+            )));
+        }
+
+        let mut shards: HashMap<usize, Wallets> = HashMap::new();
+
+        for wallet in snapshot.wallets {
+            shards
+                .entry(self.worker_id(wallet.client()) as usize)
+                .or_default()
+                .insert(wallet.client(), wallet);
+        }
+
+        for (id, wallets) in shards {
+            self.spawn(id, wallets);
+        }
+
+        self.registry = snapshot.registry.into_owned();
+        self.audit = snapshot.audit.into_owned();
+        self.wal_sequence = snapshot.wal_sequence;
+
+        Ok(self)
+    }
+
+    /// Replays the write-ahead log on top of the current state and keeps appending to it.
+    /// Should be called once the engine is configured and restored.
+    ///
+    /// Entries were logged once they passed the engine checks, so only business rules can
+    /// reject them again, any other error fails the recovery. Rejections are not reported
+    /// while replaying, they were reported before the restart.
+    pub async fn recover(mut self, path: &str) -> EngineResult<PaymentEngine> {
+        let (wal, entries) = WriteAheadLog::open(path, self.wal_sequence, self.wal_sync)?;
+
+        info!("Replaying {} write-ahead log entries", entries.len());
+
+        self.set_replaying(true);
+        let replayed = self.replay(entries).await;
+        self.set_replaying(false);
+
+        replayed?;
+
+        self.wal = Some(wal);
+
+        Ok(self)
+    }
+
+    async fn replay(&mut self, entries: Vec<LogEntry>) -> EngineResult<()> {
+        for entry in entries {
+            let result = match entry {
+                LogEntry::Transaction(tx) => self.process(tx).await,
+                LogEntry::Admin(command) => self.admin(command).await,
+            };
+
+            match result {
+                Err(error) if error.category() != ErrorCategory::Business => return Err(error),
+                _ => {}
+            }
+        }
+
+        // Workers reject asynchronously, every replayed operation is handled before unmuting.
+        self.finish_transfers(|_| true).await;
+
+        let ids: Vec<usize> = self.workers.keys().copied().collect();
+
+        for id in ids {
+            self.sync(id).await?;
+        }
+
+        Ok(())
+    }
+
+    fn set_replaying(&mut self, replaying: bool) {
+        self.replaying = replaying;
+
+        if let Some(rejections) = &self.rejections {
+            rejections.muted.store(replaying, Ordering::Release);
+        }
+    }
+
+    /// Saves the state of every worker without stopping the engine.
+    /// The write-ahead log is cleared as its entries are part of the checkpoint.
+    /// A failed worker is restarted first, a checkpoint is never saved without one of the shards.
+    pub async fn checkpoint(&mut self, path: &str) -> EngineResult<()> {
+        self.check_failed()?;
+        self.settle(true).await;
+
+        let ids: Vec<usize> = self.workers.keys().copied().collect();
+        let mut shards = HashMap::with_capacity(ids.len());
+
+        for id in ids {
+            let wallets = match self.collect(id).await {
+                Err(EngineError::WorkerFailed(..)) => {
+                    self.restart(id).await?;
+                    self.collect(id).await?
+                }
+                result => result?,
+            };
+
+            shards.insert(id, wallets);
+        }
+
+        for (id, journal) in self.journals.iter_mut() {
+            let seed = shards.get(id).into_iter().flatten();
+            journal.reset(
+                seed.map(|wallet| (wallet.client(), wallet.clone()))
+                    .collect(),
+            );
+        }
+
+        let snapshot = EngineSnapshot {
+            wallets: shards.into_values().flatten().collect(),
+            registry: Cow::Borrowed(&self.registry),
+            audit: Cow::Borrowed(&self.audit),
+            wal_sequence: self
+                .wal
+                .as_ref()
+                .map_or(self.wal_sequence, WriteAheadLog::sequence),
+        };
+
+        info!("Saving checkpoint to {}", path);
+
+        snapshot.save(path)?;
+
+        if let Some(wal) = &mut self.wal {
+            wal.reset()?;
+        }
+
+        Ok(())
+    }
+
+    /// Current balances of a client in the default asset, read from the owning worker
+    /// without stopping the engine. Operations queued before the query are applied first.
+    pub async fn account(&self, client: ClientId) -> EngineResult<Option<Account>> {
+        self.account_in(client, Asset::default()).await
+    }
+
+    /// Same as `account`, for the balances in the given asset.
+    pub async fn account_in(
+        &self,
+        client: ClientId,
+        asset: Asset,
+    ) -> EngineResult<Option<Account>> {
+        self.queries().account_in(client, asset).await
+    }
+
+    /// Handle answering the same queries while the engine itself is borrowed elsewhere.
+    pub fn queries(&self) -> EngineQueries {
+        EngineQueries::new(self.workers_size, self.routes.clone())
+    }
+
+    /// Report of the current balances, the engine keeps running.
+    pub async fn snapshot(&mut self) -> EngineResult<Report> {
+        self.settle(true).await;
+        self.check_failed()?;
 
         let mut accounts = vec![];
 
-        for handler in handlers {
-            let result: Vec<Account> = handler
+        for (id, (worker, _)) in &self.workers {
+            let (reply, response) = oneshot::channel();
+
+            worker
+                .send(Command::Balances { reply })
                 .await
-                .map_err(|_| EngineError::InternalError())?
-                .drain()
-                .map(|(_, wallet)| wallet.into())
-                .collect();
-            accounts.extend(result);
+                .map_err(|_| self.worker_failed(*id))?;
+
+            accounts.extend(response.await.map_err(|_| self.worker_failed(*id))?);
         }
 
         Ok(Report::new(accounts))
     }
 
+    async fn collect(&self, id: usize) -> EngineResult<Vec<AccountWallet>> {
+        let Some((worker, _)) = self.workers.get(&id) else {
+            return Err(self.worker_failed(id));
+        };
+
+        let (reply, response) = oneshot::channel();
+
+        worker
+            .send(Command::Snapshot { reply })
+            .await
+            .map_err(|_| self.worker_failed(id))?;
+
+        response.await.map_err(|_| self.worker_failed(id))
+    }
+
+    /// Stops every worker. A failed worker is restarted first if supervision allows it,
+    /// otherwise the report fails with the shard which is missing.
+    pub async fn report(mut self) -> Result<Report, EngineError> {
+        self.settle(true).await;
+
+        let ids: Vec<usize> = self.workers.keys().copied().collect();
+
+        let mut accounts = vec![];
+
+        for id in ids {
+            while let Some((worker, handler)) = self.detach(id) {
+                drop(worker);
+
+                match handler.await {
+                    Ok(wallets) => {
+                        accounts.extend(wallets.values().flat_map(AccountWallet::accounts));
+                        break;
+                    }
+                    Err(error) => self.revive(id, error).await?,
+                }
+            }
+        }
+
+        self.check_failed()?;
+
+        Ok(Report::new(accounts))
+    }
+
     pub async fn process(&mut self, tx: Transaction) -> EngineResult<()> {
+        self.process_from(tx, None).await
+    }
+
+    /// Same as `process`, the origin is attached to the rejection if the transaction is refused.
+    pub async fn process_from(
+        &mut self,
+        tx: Transaction,
+        origin: Option<Origin>,
+    ) -> EngineResult<()> {
+        self.process_with(tx, origin, None).await
+    }
+
+    /// Unlike `process`, which returns once the transaction is queued, the returned
+    /// acknowledgement resolves to the outcome of applying it to the wallet.
+    /// The error of the call itself is only an internal failure of the engine.
+    pub async fn submit(&mut self, tx: Transaction) -> EngineResult<Acknowledgement> {
+        self.submit_from(tx, None).await
+    }
+
+    pub async fn submit_from(
+        &mut self,
+        tx: Transaction,
+        origin: Option<Origin>,
+    ) -> EngineResult<Acknowledgement> {
         let id = self.worker_id(tx.client_id()) as usize;
+        let trade = tx.trade_id();
 
-        let (worker, _) = self
-            .workers
-            .entry(id)
-            .or_insert_with(|| init_worker(id, self.worker_buffer));
+        let (reply, response) = oneshot::channel();
 
-        worker
-            .send(tx)
+        match self.process_with(tx, origin, Some(reply)).await {
+            Ok(()) => {
+                // Nothing else drives the transfer while the caller awaits the acknowledgement.
+                self.finish_transfers(|pending| {
+                    pending.transfer.trade == trade && !pending.is_settling()
+                })
+                .await;
+
+                Ok(Acknowledgement::pending(response, self.worker_failed(id)))
+            }
+            Err(EngineError::InternalError()) => Err(EngineError::InternalError()),
+            Err(error) => Ok(Acknowledgement::ready(Err(error))),
+        }
+    }
+
+    async fn process_with(
+        &mut self,
+        tx: Transaction,
+        origin: Option<Origin>,
+        reply: Option<Reply>,
+    ) -> EngineResult<()> {
+        let (client, trade) = (tx.client_id(), tx.trade_id());
+        let step = self.is_watched(client).then(|| Step::from(&tx));
+        let recipient = tx.recipient();
+
+        self.settle(false).await;
+
+        // Operations of a client are queued after the legs of its transfers in progress,
+        // so they are applied in the same order when the log is replayed.
+        self.finish_transfers(|pending| {
+            !pending.is_settling()
+                && (pending.involves(client) || recipient.is_some_and(|to| pending.involves(to)))
+        })
+        .await;
+
+        let result = self.apply(tx, origin.clone(), reply, step.clone()).await;
+
+        if let Err(error) = &result {
+            self.reject(origin.clone(), client, Some(trade), error);
+
+            if let Some(step) = step {
+                self.record_rejection(client, step, origin, error).await;
+            }
+        }
+
+        result
+    }
+
+    async fn apply(
+        &mut self,
+        tx: Transaction,
+        origin: Option<Origin>,
+        reply: Option<Reply>,
+        step: Option<Step>,
+    ) -> EngineResult<()> {
+        if self.is_duplicate(&tx) {
+            return match self.duplicates {
+                DuplicatePolicy::Reject => Err(EngineError::DuplicateTransaction(tx.trade_id())),
+                DuplicatePolicy::Ignore => {
+                    info!("Duplicate transaction {} has been ignored", tx.trade_id());
+
+                    if let Some(reply) = reply {
+                        reply.send(Ok(())).unwrap_or_else(|_| {
+                            warn!("Transaction result has not been received");
+                        });
+                    }
+
+                    Ok(())
+                }
+            };
+        }
+
+        // Checked before the id is registered, so a corrected transaction can reuse it.
+        if let Some(amount) = tx.amount() {
+            self.policy.validate_amount(tx.trade_id(), amount)?;
+        }
+
+        // Checked against the system clock once, before the transaction is logged,
+        // so replaying the write-ahead log or a journal gives the same result.
+        if let Some(timestamp) = tx.timestamp()
+            && !self.replaying
+        {
+            self.policy
+                .check_timestamp(tx.trade_id(), timestamp, system_time())?;
+        }
+
+        self.log(|| LogEntry::Transaction(tx.clone())).await?;
+
+        // Workers are not awaited, so an id stays registered even if the wallet rejects it.
+        if tx.is_new_trade() {
+            self.registry.insert(tx.trade_id());
+        }
+
+        match WalletTransaction::try_from(tx) {
+            Ok(tx) => self.dispatch(Operation::Apply(tx), origin, reply).await,
+            Err(transfer) => {
+                let fee = fees::compute(
+                    self.fees.as_ref(),
+                    FeeKind::Transfer,
+                    transfer.amount,
+                    self.policy.precision,
+                );
+
+                self.transfer(transfer, fee, origin, reply, step).await
+            }
+        }
+    }
+
+    /// Applies an operator command, every attempt is recorded in the audit trail.
+    pub async fn admin(&mut self, command: AdminCommand) -> EngineResult<()> {
+        let operation = match command.action {
+            AdminAction::Lock => Operation::Lock {
+                client: command.client,
+                reason: LockReason::Admin {
+                    operator: command.operator.clone(),
+                    reason: command.reason.clone(),
+                },
+            },
+            AdminAction::Unlock => Operation::Unlock {
+                client: command.client,
+            },
+        };
+
+        self.log(|| LogEntry::Admin(command.clone())).await?;
+
+        self.finish_transfers(|pending| !pending.is_settling() && pending.involves(command.client))
+            .await;
+
+        let result = self.request(operation).await;
+
+        info!(
+            "Admin {} of client {} by {}: {} ({})",
+            command.action,
+            command.client,
+            command.operator,
+            command.reason,
+            if result.is_ok() {
+                "accepted"
+            } else {
+                "rejected"
+            }
+        );
+
+        let entry = AuditEntry {
+            command,
+            accepted: result.is_ok(),
+        };
+
+        if let Some(audits) = &self.audits {
+            audits.send(entry.clone()).unwrap_or_else(|_| {
+                warn!("Audit entry has not been reported");
+            });
+        }
+
+        self.audit.push(entry);
+
+        result
+    }
+
+    pub fn audit(&self) -> &[AuditEntry] {
+        &self.audit
+    }
+
+    /// Transfer legs can live on different workers, so funds are first reserved on the sender,
+    /// then credited to the recipient and finally the reservation is committed or released.
+    /// Only the reservation is queued here, the answers of the workers move the transfer
+    /// to its next leg as the engine is called.
+    async fn transfer(
+        &mut self,
+        transfer: Transfer,
+        fee: Decimal,
+        origin: Option<Origin>,
+        reply: Option<Reply>,
+        step: Option<Step>,
+    ) -> EngineResult<()> {
+        if transfer.client == transfer.to {
+            return Err(EngineError::InvalidTransfer(transfer.trade));
+        }
+
+        let (leg, response) = oneshot::channel();
+
+        let pending = PendingTransfer {
+            transfer,
+            fee,
+            phase: Phase::Reserving,
+            response,
+            origin,
+            reply,
+            step,
+            resent: false,
+        };
+
+        self.dispatch(pending.operation(), None, Some(leg)).await?;
+        self.transfers.push(pending);
+
+        Ok(())
+    }
+
+    /// Moves every transfer whose leg has been answered to its next leg.
+    async fn advance_transfers(&mut self) {
+        let mut index = 0;
+
+        while index < self.transfers.len() {
+            let answer = match self.transfers[index].response.try_recv() {
+                Ok(result) => Some(result),
+                Err(TryRecvError::Closed) => None,
+                Err(TryRecvError::Empty) => {
+                    index += 1;
+                    continue;
+                }
+            };
+
+            let pending = self.transfers.remove(index);
+            self.advance(pending, answer).await;
+        }
+    }
+
+    /// Waits for the legs of the matching transfers until none of them matches any more.
+    async fn finish_transfers(&mut self, matches: impl Fn(&PendingTransfer) -> bool) {
+        while let Some(index) = self.transfers.iter().position(&matches) {
+            let mut pending = self.transfers.remove(index);
+            let answer = (&mut pending.response).await.ok();
+
+            self.advance(pending, answer).await;
+        }
+    }
+
+    /// Sends the next leg of the transfer, a leg which cannot be sent fails like a refused one.
+    async fn advance(
+        &mut self,
+        mut pending: PendingTransfer,
+        mut answer: Option<EngineResult<()>>,
+    ) {
+        while let Some(phase) = self.next_phase(&mut pending, answer).await {
+            let (leg, response) = oneshot::channel();
+
+            pending.phase = phase;
+            pending.response = response;
+
+            match self.dispatch(pending.operation(), None, Some(leg)).await {
+                Ok(()) => {
+                    self.transfers.push(pending);
+                    return;
+                }
+                Err(error) => answer = Some(Err(error)),
+            }
+        }
+    }
+
+    /// A leg whose worker died before answering has not been applied. The reservation and
+    /// the credit fail the transfer, while a commit or release is sent once more after the
+    /// sender has been restarted, the recipient may already have been credited.
+    /// The caller is answered as soon as the credit succeeds, a settlement which fails
+    /// afterwards leaves the funds reserved and is only logged.
+    async fn next_phase(
+        &mut self,
+        pending: &mut PendingTransfer,
+        answer: Option<EngineResult<()>>,
+    ) -> Option<Phase> {
+        let id = self.worker_id(pending.operation().client_id()) as usize;
+
+        let result = match answer {
+            Some(result) => result,
+            None => {
+                let restarted = self.restart(id).await.is_ok();
+
+                if restarted && pending.is_settling() && !pending.resent {
+                    pending.resent = true;
+                    return Some(pending.phase);
+                }
+
+                Err(self.worker_failed(id))
+            }
+        };
+
+        match (pending.phase, result) {
+            (Phase::Reserving, Ok(())) => Some(Phase::Crediting),
+            (Phase::Crediting, Ok(())) => {
+                pending.answer(Ok(()));
+                Some(Phase::Committing)
+            }
+            (Phase::Reserving, Err(error)) => {
+                self.fail_transfer(pending, error).await;
+                None
+            }
+            (Phase::Crediting, Err(error)) => {
+                self.fail_transfer(pending, error).await;
+                Some(Phase::Releasing)
+            }
+            (Phase::Committing | Phase::Releasing, Ok(())) => None,
+            (Phase::Committing | Phase::Releasing, Err(error)) => {
+                error!(
+                    "Transfer reservation of client {} has not been settled: {}",
+                    pending.transfer.client, error
+                );
+                None
+            }
+        }
+    }
+
+    /// Reports a transfer refused before the recipient has been credited.
+    async fn fail_transfer(&mut self, pending: &mut PendingTransfer, error: EngineError) {
+        let (client, trade) = (pending.transfer.client, pending.transfer.trade);
+
+        pending.answer(Err(error.clone()));
+
+        self.reject(pending.origin.clone(), client, Some(trade), &error);
+
+        if let Some(step) = pending.step.take() {
+            self.record_rejection(client, step, pending.origin.clone(), &error)
+                .await;
+        }
+    }
+
+    async fn send(&mut self, operation: Operation) -> EngineResult<()> {
+        self.dispatch(operation, None, None).await
+    }
+
+    async fn request(&mut self, operation: Operation) -> EngineResult<()> {
+        let id = self.worker_id(operation.client_id()) as usize;
+
+        let (reply, response) = oneshot::channel();
+
+        self.dispatch(operation, None, Some(reply)).await?;
+
+        match response.await {
+            Ok(result) => result,
+            Err(_) => {
+                self.restart(id).await?;
+
+                Err(self.worker_failed(id))
+            }
+        }
+    }
+
+    async fn dispatch(
+        &mut self,
+        operation: Operation,
+        origin: Option<Origin>,
+        reply: Option<Reply>,
+    ) -> EngineResult<()> {
+        let id = self.worker_id(operation.client_id()) as usize;
+
+        if self.failed.contains(&id) {
+            return Err(self.worker_failed(id));
+        }
+
+        if !self.workers.contains_key(&id) {
+            self.spawn(id, Wallets::new());
+        }
+
+        let entry = (self.journals.contains_key(&id) || self.in_flight.contains_key(&id))
+            .then(|| (operation.clone(), origin.clone()));
+
+        let command = Command::Execute {
+            operation,
+            origin,
+            reply,
+        };
+
+        if let Err(SendError(command)) = self.send_command(id, command).await {
+            self.restart(id).await?;
+            self.send_command(id, command)
+                .await
+                .map_err(|_| self.worker_failed(id))?;
+        }
+
+        if let Some((operation, origin)) = entry {
+            if let Some(journal) = self.journals.get_mut(&id) {
+                journal.record(operation, origin);
+            } else if let Some(in_flight) = self.in_flight.get_mut(&id) {
+                in_flight.record(operation, origin);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn send_command(&self, id: usize, command: Command) -> Result<(), SendError<Command>> {
+        match self.workers.get(&id) {
+            Some((worker, _)) => worker.send(command).await,
+            None => Err(SendError(command)),
+        }
+    }
+
+    fn spawn(&mut self, id: usize, wallets: Wallets) {
+        let handled = Arc::new(AtomicUsize::new(0));
+
+        if self.supervision == Supervision::Restart {
+            let journal = Journal::new(
+                id,
+                self.policy,
+                self.fees.clone(),
+                self.spill.clone(),
+                wallets.clone(),
+                handled.clone(),
+            );
+
+            self.journals.insert(id, journal);
+        } else {
+            self.in_flight.insert(id, InFlight::new(handled.clone()));
+        }
+
+        self.start(id, wallets, handled);
+    }
+
+    fn start(&mut self, id: usize, wallets: Wallets, handled: Arc<AtomicUsize>) {
+        let worker = init_worker(
+            EngineWorker::new(
+                id,
+                self.policy,
+                self.fees.clone(),
+                self.spill.clone(),
+                wallets,
+            ),
+            self.worker_buffer,
+            WorkerSinks {
+                rejections: self.rejections.clone(),
+                charges: self.house.as_ref().map(|house| house.sender.clone()),
+                statement: self.statement.clone(),
+                entries: self.entries.clone(),
+            },
+            handled,
+            self.spill.is_some(),
+        );
+
+        self.update_routes(|routes| routes.insert(id, worker.0.clone()));
+        self.workers.insert(id, worker);
+    }
+
+    /// Removes the worker from the routes too, so its channel closes once the caller drops it.
+    fn detach(&mut self, id: usize) -> Option<(mpsc::Sender<Command>, JoinHandle<Wallets>)> {
+        self.update_routes(|routes| routes.remove(id));
+        self.workers.remove(&id)
+    }
+
+    fn update_routes(&self, update: impl FnOnce(&mut Routes)) {
+        match self.routes.write() {
+            Ok(mut routes) => update(&mut routes),
+            Err(poisoned) => update(&mut poisoned.into_inner()),
+        }
+    }
+
+    /// Called once a worker stopped answering, its task has ended.
+    async fn restart(&mut self, id: usize) -> EngineResult<()> {
+        let Some((worker, handler)) = self.detach(id) else {
+            return Err(self.worker_failed(id));
+        };
+
+        drop(worker);
+
+        match handler.await {
+            Ok(wallets) => {
+                let handled = match self.journals.get(&id) {
+                    Some(journal) => journal.handled(),
+                    None => {
+                        let handled = Arc::new(AtomicUsize::new(0));
+                        self.in_flight.insert(id, InFlight::new(handled.clone()));
+                        handled
+                    }
+                };
+
+                self.start(id, wallets, handled);
+
+                Ok(())
+            }
+            Err(error) => self.revive(id, error).await,
+        }
+    }
+
+    /// Rebuilds a failed worker from its journal, or marks the shard as failed without one.
+    /// The client transaction the worker failed on and the queued ones are rejected,
+    /// legs of transfers are answered as lost and sent again by the transfer if needed.
+    async fn revive(&mut self, id: usize, error: JoinError) -> EngineResult<()> {
+        let failure = self.worker_failed(id);
+
+        error!("{}: {}", failure, error);
+
+        let Some(journal) = self.journals.get_mut(&id) else {
+            self.failed.insert(id);
+            self.update_routes(|routes| routes.fail(id));
+
+            let lost = self.in_flight.remove(&id).map(InFlight::lost);
+
+            for (tx, origin) in lost.into_iter().flatten() {
+                self.reject_lost(tx, origin, &failure).await;
+            }
+
+            return Err(failure);
+        };
+
+        let (wallets, unhandled) = journal.rebuild();
+        let handled = journal.handled();
+
+        self.start(id, wallets, handled);
+
+        info!(
+            "Worker {} has been restarted, {} operations not applied",
+            id,
+            unhandled.len()
+        );
+
+        for (operation, origin) in unhandled {
+            if let Operation::Apply(tx) = operation {
+                self.reject_lost(tx, origin, &failure).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reports a client transaction dropped with a failed worker.
+    async fn reject_lost(
+        &mut self,
+        tx: WalletTransaction,
+        origin: Option<Origin>,
+        failure: &EngineError,
+    ) {
+        let tx = Transaction::from(tx);
+        let client = tx.client_id();
+
+        self.reject(origin.clone(), client, Some(tx.trade_id()), failure);
+
+        if self.is_watched(client) {
+            self.record_rejection(client, Step::from(&tx), origin, failure)
+                .await;
+        }
+    }
+
+    /// Moves the answered transfers on and credits the house account with the fees charged
+    /// by the workers so far. With `sync` every transfer is settled and every worker is asked
+    /// first, so fees of all queued operations are included.
+    /// A credit which cannot be dispatched is reported as a rejection.
+    async fn settle(&mut self, sync: bool) {
+        if sync {
+            self.finish_transfers(|_| true).await;
+        } else {
+            self.advance_transfers().await;
+        }
+
+        if self.house.is_none() {
+            return;
+        }
+
+        if sync {
+            for id in self.workers.keys() {
+                self.sync(*id).await.unwrap_or_else(|error| {
+                    warn!("Worker {} has not been synced: {}", id, error);
+                });
+            }
+        }
+
+        let Some(house) = &mut self.house else { return };
+
+        let client = house.client;
+        let mut charges = vec![];
+
+        while let Ok(charge) = house.charges.try_recv() {
+            charges.push(charge);
+        }
+
+        for charge in charges {
+            let trade = charge.trade;
+
+            let collect = Operation::Collect {
+                client,
+                trade,
+                asset: charge.asset,
+                amount: charge.amount,
+            };
+
+            if let Err(error) = self.send(collect).await {
+                self.reject(None, client, Some(trade), &error);
+            }
+        }
+    }
+
+    async fn sync(&self, id: usize) -> EngineResult<()> {
+        let (reply, response) = oneshot::channel();
+
+        self.send_command(id, Command::Sync { reply })
             .await
-            .map_err(|_| EngineError::InternalError())
+            .map_err(|_| self.worker_failed(id))?;
+
+        response.await.map_err(|_| self.worker_failed(id))
+    }
+
+    fn reject(
+        &self,
+        origin: Option<Origin>,
+        client: ClientId,
+        trade: Option<TransactionId>,
+        error: &EngineError,
+    ) {
+        if let Some(rejections) = &self.rejections {
+            rejections.send(Rejection::new(origin, Some(client), trade, error));
+        }
+    }
+
+    fn is_watched(&self, client: ClientId) -> bool {
+        self.statement
+            .as_ref()
+            .is_some_and(|statement| statement.client == client)
+    }
+
+    /// Rejections decided by the engine are added to the statement by the worker owning the
+    /// client, so they keep their order with the steps the worker handles.
+    async fn record_rejection(
+        &mut self,
+        client: ClientId,
+        step: Step,
+        origin: Option<Origin>,
+        error: &EngineError,
+    ) {
+        let id = self.worker_id(client) as usize;
+
+        if !self.workers.contains_key(&id) && !self.failed.contains(&id) {
+            self.spawn(id, Wallets::new());
+        }
+
+        let command = Command::Rejected {
+            client,
+            step,
+            origin,
+            error: error.clone(),
+        };
+
+        if self.send_command(id, command).await.is_err() {
+            warn!("Statement line has not been recorded");
+        }
+    }
+
+    fn check_failed(&self) -> EngineResult<()> {
+        match self.failed.iter().min() {
+            Some(id) => Err(self.worker_failed(*id)),
+            None => Ok(()),
+        }
+    }
+
+    fn worker_failed(&self, id: usize) -> EngineError {
+        EngineError::WorkerFailed(id, self.workers_size)
+    }
+
+    async fn log(&mut self, entry: impl FnOnce() -> LogEntry) -> EngineResult<()> {
+        match &mut self.wal {
+            Some(wal) => wal.append(&entry()).await,
+            None => Ok(()),
+        }
+    }
+
+    fn is_duplicate(&self, tx: &Transaction) -> bool {
+        tx.is_new_trade() && self.registry.contains(tx.trade_id())
     }
 
     fn worker_id(&self, id: ClientId) -> u16 {
@@ -77,25 +1142,260 @@ impl PaymentEngine {
     }
 }
 
-fn init_worker(id: usize, buffer: usize) -> (mpsc::Sender<Transaction>, JoinHandle<Wallets>) {
-    let (tx, mut rx): (mpsc::Sender<Transaction>, mpsc::Receiver<Transaction>) =
-        mpsc::channel::<Transaction>(buffer);
+fn system_time() -> Timestamp {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
 
-    let accounts = tokio::spawn(async move {
-        info!("Initialize worker with id {}", id);
+    Timestamp(now)
+}
 
-        let mut worker = EngineWorker::new(id);
+/// Channels a worker reports to, next to the answers of the commands.
+struct WorkerSinks {
+    rejections: Option<RejectionSink>,
+    charges: Option<Charges>,
+    statement: Option<Statement>,
+    entries: Option<Entries>,
+}
 
-        while let Some(tx) = rx.recv().await {
-            info!("Processing transaction by worker {}", worker.id);
+/// Workers which page transactions out to a store read and write it while handling
+/// commands, so they get a thread of the blocking pool instead of a runtime task.
+fn init_worker(
+    mut worker: EngineWorker,
+    buffer: usize,
+    sinks: WorkerSinks,
+    handled: Arc<AtomicUsize>,
+    blocking: bool,
+) -> (mpsc::Sender<Command>, JoinHandle<Wallets>) {
+    let (tx, mut rx): (mpsc::Sender<Command>, mpsc::Receiver<Command>) =
+        mpsc::channel::<Command>(buffer);
 
-            worker.handle(tx).unwrap_or_else(|error| {
-                warn!("Transaction has been rejected: {:?}", error);
-            });
-        }
+    // A failing worker refuses new commands before its replies are dropped,
+    // so whoever has seen it fail never queues a command behind it.
+    let accounts = if blocking {
+        task::spawn_blocking(move || {
+            info!("Initialize blocking worker with id {}", worker.id);
+
+            while let Some(command) = rx.blocking_recv() {
+                if let Err(payload) = sinks.handle(&mut worker, command, &handled) {
+                    rx.close();
+                    panic::resume_unwind(payload);
+                }
+            }
 
-        worker.accounts()
-    });
+            worker.accounts()
+        })
+    } else {
+        tokio::spawn(async move {
+            info!("Initialize worker with id {}", worker.id);
+
+            while let Some(command) = rx.recv().await {
+                if let Err(payload) = sinks.handle(&mut worker, command, &handled) {
+                    rx.close();
+                    panic::resume_unwind(payload);
+                }
+            }
+
+            worker.accounts()
+        })
+    };
 
     (tx, accounts)
 }
+
+impl WorkerSinks {
+    /// Handles a single command, the payload of a panic is returned to the caller.
+    fn handle(
+        &self,
+        worker: &mut EngineWorker,
+        command: Command,
+        handled: &AtomicUsize,
+    ) -> Result<(), Box<dyn Any + Send>> {
+        match command {
+            Command::Execute {
+                operation,
+                origin,
+                reply,
+            } => {
+                info!("Processing transaction by worker {}", worker.id);
+
+                let (client, trade) = (operation.client_id(), operation.trade_id());
+
+                // Client transactions are always reported, internal requests only answered.
+                let reported = reply.is_none() || matches!(operation, Operation::Apply(_));
+
+                let step = self
+                    .statement
+                    .as_ref()
+                    .filter(|statement| statement.client == client)
+                    .and_then(|_| worker.step(&operation));
+
+                let result = panic::catch_unwind(AssertUnwindSafe(|| worker.handle(operation)))?;
+
+                for entry in worker.take_posted() {
+                    if let Some(entries) = &self.entries {
+                        entries.send(entry).unwrap_or_else(|_| {
+                            warn!("Journal entry has not been reported");
+                        });
+                    }
+                }
+
+                let result = result.map(|charged| {
+                    if let (Some(charge), Some(charges)) = (charged, &self.charges) {
+                        charges.send(charge).unwrap_or_else(|_| {
+                            warn!("Fee charge has not been reported");
+                        });
+                    }
+                });
+
+                if let Err(error) = &result
+                    && reported
+                {
+                    warn!("Transaction has been rejected: {:?}", error);
+
+                    if let Some(rejections) = &self.rejections {
+                        rejections.send(Rejection::new(origin.clone(), Some(client), trade, error));
+                    }
+                }
+
+                if let (Some(statement), Some(step)) = (&self.statement, step)
+                    && (result.is_ok() || reported)
+                {
+                    let error = result.as_ref().err();
+                    let line = worker.statement_line(client, step, origin.as_ref(), error);
+
+                    statement.sender.send(line).unwrap_or_else(|_| {
+                        warn!("Statement line has not been reported");
+                    });
+                }
+
+                if let Some(reply) = reply {
+                    reply.send(result).unwrap_or_else(|_| {
+                        warn!("Transaction result has not been received");
+                    });
+                }
+
+                handled.fetch_add(1, Ordering::Release);
+            }
+            Command::Account {
+                client,
+                asset,
+                reply,
+            } => {
+                reply
+                    .send(worker.account(client, &asset))
+                    .unwrap_or_else(|_| {
+                        warn!("Account balances have not been received");
+                    });
+            }
+            Command::Rejected {
+                client,
+                step,
+                origin,
+                error,
+            } => {
+                if let Some(statement) = &self.statement {
+                    let step = worker.locate(client, step);
+                    let line = worker.statement_line(client, step, origin.as_ref(), Some(&error));
+
+                    statement.sender.send(line).unwrap_or_else(|_| {
+                        warn!("Statement line has not been reported");
+                    });
+                }
+            }
+            Command::Balances { reply } => {
+                reply.send(worker.balances()).unwrap_or_else(|_| {
+                    warn!("Worker balances have not been received");
+                });
+            }
+            Command::Sync { reply } => {
+                reply.send(()).unwrap_or_else(|_| {
+                    warn!("Worker sync has not been received");
+                });
+            }
+            Command::Snapshot { reply } => {
+                reply.send(worker.snapshot()).unwrap_or_else(|_| {
+                    warn!("Worker snapshot has not been received");
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::command::Command;
+    use crate::core::engine::{PaymentEngine, Wallets};
+    use crate::core::supervisor::Supervision;
+    use crate::errors::EngineResult;
+    use crate::model::asset::Asset;
+    use crate::model::client::ClientId;
+    use crate::model::trade::{Transaction, TransactionId};
+    use rust_decimal_macros::dec;
+    use tokio::sync::mpsc;
+    use tokio::task::JoinHandle;
+
+    /// Takes the place of a worker and panics on the first command it receives.
+    fn faulty_worker() -> (mpsc::Sender<Command>, JoinHandle<Wallets>) {
+        let (worker, mut commands) = mpsc::channel(1);
+
+        let handler = tokio::spawn(async move {
+            let _command = commands.recv().await;
+            panic!("Injected worker fault");
+        });
+
+        (worker, handler)
+    }
+
+    #[tokio::test]
+    async fn test_commit_transfer_after_sender_failed_on_commit() -> EngineResult<()> {
+        let (sender, mut rejections) = mpsc::unbounded_channel();
+
+        let mut engine = PaymentEngine::new(2)
+            .with_supervision(Supervision::Restart)
+            .with_rejections(sender);
+
+        engine
+            .process(Transaction::Deposit {
+                client: ClientId(1),
+                trade: TransactionId(1),
+                amount: dec!(10),
+                asset: Asset::default(),
+                timestamp: None,
+            })
+            .await?;
+
+        engine
+            .process(Transaction::Transfer {
+                client: ClientId(1),
+                to: ClientId(2),
+                trade: TransactionId(2),
+                amount: dec!(4),
+                asset: Asset::default(),
+            })
+            .await?;
+
+        // The reservation is applied, the next command of the sender is the commit.
+        engine.sync(1).await?;
+
+        if let Some((worker, handler)) = engine.detach(1) {
+            drop(worker);
+            handler.await.expect("Worker has stopped");
+        }
+
+        engine.workers.insert(1, faulty_worker());
+
+        engine.snapshot().await?;
+
+        let sender = engine.account(ClientId(1)).await?.unwrap();
+        let recipient = engine.account(ClientId(2)).await?.unwrap();
+
+        assert_eq!((sender.available, sender.total), (dec!(6), dec!(6)));
+        assert_eq!((recipient.available, recipient.total), (dec!(4), dec!(4)));
+        assert!(rejections.try_recv().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use crate::model::asset::Asset;
+use crate::model::client::ClientId;
+use crate::model::trade::TransactionId;
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::Deserialize;
+
+/// Transaction types a fee can be charged on. Transfer fees are paid by the sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeeKind {
+    Deposit,
+    Withdrawal,
+    Transfer,
+    Chargeback,
+}
+
+/// Decides the fee of a transaction, plugged into the engine with `PaymentEngine::with_fees`.
+pub trait FeePolicy: Send + Sync {
+    /// Fee for a transaction of the given kind and amount, zero when it is free.
+    fn fee(&self, kind: FeeKind, amount: Decimal) -> Decimal;
+}
+
+/// Default policy, nothing is charged.
+pub struct NoFees;
+
+impl FeePolicy for NoFees {
+    fn fee(&self, _: FeeKind, _: Decimal) -> Decimal {
+        Decimal::ZERO
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeRule {
+    Flat(Decimal),
+    /// Percent of the amount, `1.5` charges 1.5%.
+    Percentage(Decimal),
+    /// The rule of the last tier starting at or below the amount, tiers are sorted by `from`.
+    Tiered(Vec<FeeTier>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeeTier {
+    pub from: Decimal,
+    pub fee: FeeRule,
+}
+
+impl FeeRule {
+    pub fn fee(&self, amount: Decimal) -> Decimal {
+        match self {
+            FeeRule::Flat(fee) => *fee,
+            FeeRule::Percentage(percent) => amount * percent / Decimal::ONE_HUNDRED,
+            FeeRule::Tiered(tiers) => tiers
+                .iter()
+                .rev()
+                .find(|tier| tier.from <= amount)
+                .map_or(Decimal::ZERO, |tier| tier.fee.fee(amount)),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            FeeRule::Flat(fee) if fee.is_sign_negative() => {
+                Err(format!("flat fee cannot be negative, got {}", fee))
+            }
+            FeeRule::Percentage(percent) if percent.is_sign_negative() => Err(format!(
+                "percentage fee cannot be negative, got {}",
+                percent
+            )),
+            FeeRule::Tiered(tiers) => {
+                if tiers.windows(2).any(|pair| pair[0].from >= pair[1].from) {
+                    return Err("fee tiers have to be sorted by from".to_string());
+                }
+
+                tiers.iter().try_for_each(|tier| tier.fee.validate())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Fee rule per transaction type, types without a rule are free.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeSchedule {
+    pub deposit: Option<FeeRule>,
+    pub withdrawal: Option<FeeRule>,
+    pub transfer: Option<FeeRule>,
+    pub chargeback: Option<FeeRule>,
+}
+
+impl FeeSchedule {
+    pub fn is_empty(&self) -> bool {
+        self.rules().all(|rule| rule.is_none())
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        self.rules().flatten().try_for_each(FeeRule::validate)
+    }
+
+    fn rules(&self) -> impl Iterator<Item = Option<&FeeRule>> {
+        [
+            self.deposit.as_ref(),
+            self.withdrawal.as_ref(),
+            self.transfer.as_ref(),
+            self.chargeback.as_ref(),
+        ]
+        .into_iter()
+    }
+}
+
+impl FeePolicy for FeeSchedule {
+    fn fee(&self, kind: FeeKind, amount: Decimal) -> Decimal {
+        let rule = match kind {
+            FeeKind::Deposit => &self.deposit,
+            FeeKind::Withdrawal => &self.withdrawal,
+            FeeKind::Transfer => &self.transfer,
+            FeeKind::Chargeback => &self.chargeback,
+        };
+
+        rule.as_ref().map_or(Decimal::ZERO, |rule| rule.fee(amount))
+    }
+}
+
+/// Fee debited from a wallet, credited to the house account by the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeCharge {
+    pub client: ClientId,
+    pub trade: TransactionId,
+    pub asset: Asset,
+    pub amount: Decimal,
+}
+
+/// Fees are rounded half away from zero to the precision of amounts, never below zero.
+pub(crate) fn compute(
+    policy: &dyn FeePolicy,
+    kind: FeeKind,
+    amount: Decimal,
+    precision: u32,
+) -> Decimal {
+    policy
+        .fee(kind, amount)
+        .round_dp_with_strategy(precision, RoundingStrategy::MidpointAwayFromZero)
+        .max(Decimal::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::fees::{FeeKind, FeePolicy, FeeRule, FeeSchedule, FeeTier, compute};
+    use rust_decimal_macros::dec;
+
+    fn tiered() -> FeeRule {
+        FeeRule::Tiered(vec![
+            FeeTier {
+                from: dec!(0),
+                fee: FeeRule::Flat(dec!(1)),
+            },
+            FeeTier {
+                from: dec!(100),
+                fee: FeeRule::Percentage(dec!(0.5)),
+            },
+        ])
+    }
+
+    #[test]
+    fn test_fee_rules() {
+        assert_eq!(FeeRule::Flat(dec!(0.25)).fee(dec!(10)), dec!(0.25));
+        assert_eq!(FeeRule::Percentage(dec!(1.5)).fee(dec!(10)), dec!(0.15));
+        assert_eq!(tiered().fee(dec!(50)), dec!(1));
+        assert_eq!(tiered().fee(dec!(300)), dec!(1.5));
+    }
+
+    #[test]
+    fn test_fee_per_transaction_type() {
+        let schedule = FeeSchedule {
+            withdrawal: Some(FeeRule::Flat(dec!(0.5))),
+            ..FeeSchedule::default()
+        };
+
+        assert_eq!(schedule.fee(FeeKind::Withdrawal, dec!(10)), dec!(0.5));
+        assert_eq!(schedule.fee(FeeKind::Deposit, dec!(10)), dec!(0));
+    }
+
+    #[test]
+    fn test_fee_is_rounded_to_precision() {
+        let schedule = FeeSchedule {
+            chargeback: Some(FeeRule::Percentage(dec!(1))),
+            ..FeeSchedule::default()
+        };
+
+        assert_eq!(
+            compute(&schedule, FeeKind::Chargeback, dec!(1.2345), 4),
+            dec!(0.0123)
+        );
+        assert_eq!(
+            compute(&schedule, FeeKind::Chargeback, dec!(0.5), 2),
+            dec!(0.01)
+        );
+    }
+
+    #[test]
+    fn test_unsorted_tiers_are_invalid() {
+        let schedule = FeeSchedule {
+            deposit: Some(FeeRule::Tiered(vec![
+                FeeTier {
+                    from: dec!(10),
+                    fee: FeeRule::Flat(dec!(1)),
+                },
+                FeeTier {
+                    from: dec!(5),
+                    fee: FeeRule::Flat(dec!(2)),
+                },
+            ])),
+            ..FeeSchedule::default()
+        };
+
+        assert!(schedule.validate().is_err());
+    }
+}
@@ -0,0 +1,420 @@
+use crate::core::wallet::TradeRecord;
+use crate::errors::{EngineError, EngineResult, ErrorSource};
+use crate::model::client::ClientId;
+use crate::model::trade::TransactionId;
+use redb::{Database, Durability, ReadableDatabase, Table, TableDefinition, TableError};
+use serde::ser::{self, SerializeMap};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use tracing::warn;
+
+/// Where the history of deposits and withdrawals is kept, they can be disputed at any time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(tag = "store", rename_all = "lowercase", deny_unknown_fields)]
+pub enum HistoryConfig {
+    /// Every transaction stays in memory.
+    #[default]
+    Memory,
+    /// Every transaction is written to the file as soon as it is handled.
+    Disk { path: String },
+    /// The `capacity` most recently used transactions of every client stay in memory,
+    /// older ones are written to the file.
+    Spill { path: String, capacity: usize },
+}
+
+/// Key-value store of transactions paged out of the wallets, shared by all workers.
+/// Values are opaque, a stored transaction is read back only by the wallet which wrote it.
+pub trait TradeStore: Send + Sync {
+    fn put(&self, client: ClientId, id: TransactionId, value: &[u8]) -> EngineResult<()>;
+
+    fn get(&self, client: ClientId, id: TransactionId) -> EngineResult<Option<Vec<u8>>>;
+
+    /// Called once the transaction can no longer be disputed.
+    fn remove(&self, client: ClientId, id: TransactionId) -> EngineResult<()>;
+
+    /// Every value stored for the client, used when the wallet is saved to a snapshot.
+    fn scan(&self, client: ClientId) -> EngineResult<Vec<(TransactionId, Vec<u8>)>>;
+}
+
+/// Transactions kept in memory by every wallet, the rest is paged out to the store.
+#[derive(Clone)]
+pub struct Spill {
+    pub store: Arc<dyn TradeStore>,
+    pub capacity: usize,
+}
+
+/// Transactions paged out by all wallets, keyed by client and tx id.
+const TRADES: TableDefinition<(u16, u32), &[u8]> = TableDefinition::new("trades");
+
+/// Writes between two compactions of the file.
+pub const COMPACTION_INTERVAL: u64 = 10_000;
+
+/// Embedded [redb](https://docs.rs/redb) database. Reads run concurrently, writes are
+/// serialized by the database. A new value of a key replaces the old one and a removed one
+/// frees its pages, the file is compacted every `COMPACTION_INTERVAL` writes.
+/// Commits are not synced, the file is recreated on every start.
+pub struct DiskStore {
+    database: RwLock<Database>,
+    writes: AtomicU64,
+}
+
+impl DiskStore {
+    pub fn create(path: &str) -> EngineResult<DiskStore> {
+        match fs::remove_file(path) {
+            Err(error) if error.kind() != ErrorKind::NotFound => return Err(error.into()),
+            _ => {}
+        }
+
+        let database = Database::create(path).map_err(store_error)?;
+
+        Ok(DiskStore {
+            database: RwLock::new(database),
+            writes: AtomicU64::new(0),
+        })
+    }
+
+    fn read(&self) -> EngineResult<RwLockReadGuard<'_, Database>> {
+        self.database
+            .read()
+            .map_err(|_| EngineError::InternalError())
+    }
+
+    fn write<F>(&self, update: F) -> EngineResult<()>
+    where
+        F: FnOnce(&mut Table<(u16, u32), &[u8]>) -> Result<(), redb::StorageError>,
+    {
+        {
+            let database = self.read()?;
+            let mut transaction = database.begin_write().map_err(store_error)?;
+            transaction
+                .set_durability(Durability::None)
+                .map_err(store_error)?;
+
+            {
+                let mut table = transaction.open_table(TRADES).map_err(store_error)?;
+                update(&mut table).map_err(store_error)?;
+            }
+
+            transaction.commit().map_err(store_error)?;
+        }
+
+        let writes = self.writes.fetch_add(1, Ordering::AcqRel) + 1;
+
+        // The write is done, a failed compaction is retried after the next interval.
+        if writes.is_multiple_of(COMPACTION_INTERVAL)
+            && let Err(error) = self.compact()
+        {
+            warn!("History store has not been compacted: {}", error);
+        }
+
+        Ok(())
+    }
+
+    /// Syncs the pages freed since the last compaction and moves the live values
+    /// to the start of the file, so it shrinks as transactions are removed.
+    pub fn compact(&self) -> EngineResult<()> {
+        let mut database = self
+            .database
+            .write()
+            .map_err(|_| EngineError::InternalError())?;
+
+        // Unsynced commits keep the last synced one alive until the next synced commit.
+        database
+            .begin_write()
+            .map_err(store_error)?
+            .commit()
+            .map_err(store_error)?;
+        database.compact().map_err(store_error)?;
+
+        Ok(())
+    }
+}
+
+impl TradeStore for DiskStore {
+    fn put(&self, client: ClientId, id: TransactionId, value: &[u8]) -> EngineResult<()> {
+        self.write(|table| table.insert((client.0, id.0), value).map(|_| ()))
+    }
+
+    fn get(&self, client: ClientId, id: TransactionId) -> EngineResult<Option<Vec<u8>>> {
+        let database = self.read()?;
+        let transaction = database.begin_read().map_err(store_error)?;
+
+        let table = match transaction.open_table(TRADES) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(None),
+            Err(error) => return Err(store_error(error)),
+        };
+
+        let value = table.get((client.0, id.0)).map_err(store_error)?;
+
+        Ok(value.map(|value| value.value().to_vec()))
+    }
+
+    fn remove(&self, client: ClientId, id: TransactionId) -> EngineResult<()> {
+        self.write(|table| table.remove((client.0, id.0)).map(|_| ()))
+    }
+
+    fn scan(&self, client: ClientId) -> EngineResult<Vec<(TransactionId, Vec<u8>)>> {
+        let database = self.read()?;
+        let transaction = database.begin_read().map_err(store_error)?;
+
+        let table = match transaction.open_table(TRADES) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(vec![]),
+            Err(error) => return Err(store_error(error)),
+        };
+
+        table
+            .range((client.0, u32::MIN)..=(client.0, u32::MAX))
+            .map_err(store_error)?
+            .map(|entry| {
+                let (key, value) = entry.map_err(store_error)?;
+
+                Ok((TransactionId(key.value().1), value.value().to_vec()))
+            })
+            .collect()
+    }
+}
+
+fn store_error(error: impl Into<redb::Error>) -> EngineError {
+    EngineError::Io(ErrorSource::new(error.into()))
+}
+
+/// Deposits and withdrawals of a wallet. Without a spill all of them stay in memory,
+/// with one the least recently used are paged out once the wallet holds more than the capacity.
+/// A paged out transaction is read back when it is disputed.
+#[derive(Clone)]
+pub(crate) struct TradeHistory {
+    client: ClientId,
+    spill: Option<Spill>,
+    hot: HashMap<TransactionId, (TradeRecord, u64)>,
+    usage: BTreeMap<u64, TransactionId>,
+    tick: u64,
+}
+
+impl TradeHistory {
+    pub(crate) fn new(client: ClientId) -> Self {
+        Self {
+            client,
+            spill: None,
+            hot: HashMap::new(),
+            usage: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub(crate) fn set_spill(&mut self, client: ClientId, spill: Option<Spill>) {
+        self.client = client;
+        self.spill = spill;
+        self.trim();
+    }
+
+    /// Reads a transaction without bringing it back to memory.
+    pub(crate) fn peek(&self, id: TransactionId) -> EngineResult<Option<TradeRecord>> {
+        match self.hot.get(&id) {
+            Some((record, _)) => Ok(Some(record.clone())),
+            None => self.load(id),
+        }
+    }
+
+    /// Reads a transaction and keeps it in memory, it is about to change.
+    pub(crate) fn get(&mut self, id: TransactionId) -> EngineResult<Option<&mut TradeRecord>> {
+        if !self.hot.contains_key(&id) {
+            let Some(record) = self.load(id)? else {
+                return Ok(None);
+            };
+
+            self.hot.insert(id, (record, 0));
+        }
+
+        self.touch(id);
+
+        Ok(self.hot.get_mut(&id).map(|(record, _)| record))
+    }
+
+    pub(crate) fn insert(&mut self, id: TransactionId, record: TradeRecord) {
+        if let Some((_, used)) = self.hot.insert(id, (record, 0)) {
+            self.usage.remove(&used);
+        }
+
+        self.touch(id);
+    }
+
+    pub(crate) fn remove(&mut self, id: TransactionId) -> EngineResult<()> {
+        if let Some((_, used)) = self.hot.remove(&id) {
+            self.usage.remove(&used);
+        }
+
+        match &self.spill {
+            Some(spill) => spill.store.remove(self.client, id),
+            None => Ok(()),
+        }
+    }
+
+    /// Pages out the least recently used transactions above the capacity. A transaction
+    /// which cannot be written stays in memory, so nothing is lost when the store fails.
+    pub(crate) fn trim(&mut self) {
+        let Some(spill) = self.spill.clone() else {
+            return;
+        };
+
+        while self.hot.len() > spill.capacity {
+            let Some((&tick, &id)) = self.usage.first_key_value() else {
+                return;
+            };
+
+            let written = self.hot.get(&id).map(|(record, _)| {
+                serde_json::to_vec(record)
+                    .map_err(|e| EngineError::Io(ErrorSource::new(e)))
+                    .and_then(|value| spill.store.put(self.client, id, &value))
+            });
+
+            if let Some(Err(error)) = written {
+                warn!("Transaction {} has not been paged out: {}", id, error);
+                return;
+            }
+
+            self.usage.remove(&tick);
+            self.hot.remove(&id);
+        }
+    }
+
+    fn touch(&mut self, id: TransactionId) {
+        self.tick += 1;
+
+        if let Some((_, used)) = self.hot.get_mut(&id) {
+            self.usage.remove(used);
+            *used = self.tick;
+            self.usage.insert(self.tick, id);
+        }
+    }
+
+    fn load(&self, id: TransactionId) -> EngineResult<Option<TradeRecord>> {
+        let Some(spill) = &self.spill else {
+            return Ok(None);
+        };
+
+        spill
+            .store
+            .get(self.client, id)?
+            .map(|value| decode(&value))
+            .transpose()
+    }
+
+    fn records(&self) -> EngineResult<HashMap<TransactionId, TradeRecord>> {
+        let mut records = HashMap::new();
+
+        if let Some(spill) = &self.spill {
+            for (id, value) in spill.store.scan(self.client)? {
+                records.insert(id, decode(&value)?);
+            }
+        }
+
+        for (id, (record, _)) in &self.hot {
+            records.insert(*id, record.clone());
+        }
+
+        Ok(records)
+    }
+}
+
+fn decode(value: &[u8]) -> EngineResult<TradeRecord> {
+    serde_json::from_slice(value).map_err(|e| EngineError::Io(ErrorSource::new(e)))
+}
+
+/// Saved as a map of all transactions, including the paged out ones.
+impl Serialize for TradeHistory {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let records = self.records().map_err(ser::Error::custom)?;
+
+        let mut map = serializer.serialize_map(Some(records.len()))?;
+
+        for (id, record) in &records {
+            map.serialize_entry(id, record)?;
+        }
+
+        map.end()
+    }
+}
+
+/// Loaded into memory, the worker pages them out again once it sets the spill of the wallet.
+impl<'de> Deserialize<'de> for TradeHistory {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let records = HashMap::<TransactionId, TradeRecord>::deserialize(deserializer)?;
+
+        let mut history = TradeHistory::new(ClientId(0));
+
+        for (id, record) in records {
+            history.insert(id, record);
+        }
+
+        Ok(history)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::history::{DiskStore, TradeStore};
+    use crate::model::client::ClientId;
+    use crate::model::trade::TransactionId;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_disk_store_reads_latest_value() -> anyhow::Result<()> {
+        let directory = TempDir::new()?;
+        let path = directory.path().join("history.db");
+        let store = DiskStore::create(path.to_str().unwrap())?;
+
+        store.put(ClientId(1), TransactionId(1), b"first")?;
+        store.put(ClientId(2), TransactionId(1), b"other")?;
+        store.put(ClientId(1), TransactionId(1), b"second")?;
+        store.put(ClientId(1), TransactionId(3), b"removed")?;
+        store.remove(ClientId(1), TransactionId(3))?;
+
+        assert_eq!(
+            store.get(ClientId(1), TransactionId(1))?,
+            Some(b"second".to_vec())
+        );
+        assert_eq!(store.get(ClientId(1), TransactionId(2))?, None);
+        assert_eq!(store.get(ClientId(1), TransactionId(3))?, None);
+        assert_eq!(
+            store.scan(ClientId(2))?,
+            vec![(TransactionId(1), b"other".to_vec())]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_disk_store_shrinks_once_compacted() -> anyhow::Result<()> {
+        let directory = TempDir::new()?;
+        let path = directory.path().join("history.db");
+        let store = DiskStore::create(path.to_str().unwrap())?;
+
+        for id in 0..200 {
+            store.put(ClientId(1), TransactionId(id), &[1; 1024])?;
+        }
+
+        store.compact()?;
+        let before = fs::metadata(&path)?.len();
+
+        for id in 1..200 {
+            store.remove(ClientId(1), TransactionId(id))?;
+        }
+
+        store.compact()?;
+
+        assert!(fs::metadata(&path)?.len() < before);
+        assert_eq!(
+            store.scan(ClientId(1))?,
+            vec![(TransactionId(0), vec![1; 1024])]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use crate::errors::{EngineError, EngineResult};
+use crate::model::asset::Asset;
+use crate::model::ledger::{JournalEntry, LedgerAccount};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::mem;
+
+/// Running balance of one account of a wallet, in one asset.
+#[derive(Serialize, Deserialize)]
+struct Balance {
+    asset: Asset,
+    account: LedgerAccount,
+    amount: Decimal,
+}
+
+/// Balances of a wallet per asset and account. Posted entries are kept only until the worker
+/// takes them for the journal sink, so only the balances are part of the wallet state.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<Balance>", into = "Vec<Balance>")]
+pub(crate) struct Ledger {
+    balances: BTreeMap<(Asset, LedgerAccount), Decimal>,
+    posted: Vec<JournalEntry>,
+}
+
+impl Ledger {
+    /// Posts all entries or none of them, fails if a balance would overflow.
+    /// Entries with a zero amount are not recorded.
+    pub(crate) fn post(&mut self, entries: &[JournalEntry]) -> EngineResult<()> {
+        let entries: Vec<&JournalEntry> = entries
+            .iter()
+            .filter(|entry| !entry.amount.is_zero())
+            .collect();
+
+        let mut staged: BTreeMap<(Asset, LedgerAccount), Decimal> = BTreeMap::new();
+
+        for entry in &entries {
+            for (account, amount) in [(entry.debit, -entry.amount), (entry.credit, entry.amount)] {
+                let key = (entry.asset.clone(), account);
+                let balance = match staged.get(&key) {
+                    Some(balance) => *balance,
+                    None => self.balance(&entry.asset, account),
+                };
+
+                let balance = balance
+                    .checked_add(amount)
+                    .ok_or(EngineError::BalanceOverflow(entry.tx))?;
+
+                staged.insert(key, balance);
+            }
+        }
+
+        self.balances.extend(staged);
+        self.posted.extend(entries.into_iter().cloned());
+
+        Ok(())
+    }
+
+    /// Credits minus debits of the account, in the given asset.
+    pub(crate) fn balance(&self, asset: &Asset, account: LedgerAccount) -> Decimal {
+        self.balances
+            .get(&(asset.clone(), account))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Assets with at least one entry, in order.
+    pub(crate) fn assets(&self) -> Vec<Asset> {
+        let mut assets: Vec<Asset> = self
+            .balances
+            .keys()
+            .map(|(asset, _)| asset.clone())
+            .collect();
+        assets.dedup();
+        assets
+    }
+
+    /// Entries posted since the last call, in order.
+    pub(crate) fn take_posted(&mut self) -> Vec<JournalEntry> {
+        mem::take(&mut self.posted)
+    }
+}
+
+impl From<Vec<Balance>> for Ledger {
+    fn from(balances: Vec<Balance>) -> Self {
+        Ledger {
+            balances: balances
+                .into_iter()
+                .map(|balance| ((balance.asset, balance.account), balance.amount))
+                .collect(),
+            posted: vec![],
+        }
+    }
+}
+
+impl From<Ledger> for Vec<Balance> {
+    fn from(ledger: Ledger) -> Self {
+        ledger
+            .balances
+            .into_iter()
+            .map(|((asset, account), amount)| Balance {
+                asset,
+                account,
+                amount,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::ledger::Ledger;
+    use crate::errors::EngineError;
+    use crate::model::asset::Asset;
+    use crate::model::client::ClientId;
+    use crate::model::ledger::{EntryKind, JournalEntry, LedgerAccount};
+    use crate::model::trade::TransactionId;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn entry(debit: LedgerAccount, credit: LedgerAccount, amount: Decimal) -> JournalEntry {
+        JournalEntry {
+            client: ClientId(1),
+            tx: TransactionId(1),
+            kind: EntryKind::Deposit,
+            asset: Asset::default(),
+            debit,
+            credit,
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_every_asset_balances_to_zero() {
+        let mut ledger = Ledger::default();
+
+        let confirmation = ledger.post(&[
+            entry(LedgerAccount::External, LedgerAccount::Available, dec!(5)),
+            entry(LedgerAccount::Available, LedgerAccount::Held, dec!(2)),
+        ]);
+
+        assert!(confirmation.is_ok());
+
+        let accounts = [
+            LedgerAccount::Available,
+            LedgerAccount::Held,
+            LedgerAccount::Reserved,
+            LedgerAccount::Fees,
+            LedgerAccount::External,
+        ];
+        let sum: Decimal = accounts
+            .iter()
+            .map(|account| ledger.balance(&Asset::default(), *account))
+            .sum();
+
+        assert_eq!(
+            ledger.balance(&Asset::default(), LedgerAccount::Available),
+            dec!(3)
+        );
+        assert_eq!(
+            ledger.balance(&Asset::default(), LedgerAccount::External),
+            dec!(-5)
+        );
+        assert_eq!(sum, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_posted_entries_are_taken_once() {
+        let mut ledger = Ledger::default();
+
+        let confirmation = ledger.post(&[
+            entry(LedgerAccount::External, LedgerAccount::Available, dec!(5)),
+            entry(LedgerAccount::Available, LedgerAccount::Fees, Decimal::ZERO),
+        ]);
+
+        assert!(confirmation.is_ok());
+        assert_eq!(ledger.take_posted().len(), 1);
+        assert!(ledger.take_posted().is_empty());
+
+        let restored = Ledger::from(Vec::from(ledger.clone()));
+
+        assert_eq!(restored.balances, ledger.balances);
+    }
+
+    #[test]
+    fn test_overflowing_entries_are_not_posted() {
+        let mut ledger = Ledger::default();
+
+        let confirmation = ledger.post(&[entry(
+            LedgerAccount::External,
+            LedgerAccount::Available,
+            Decimal::MAX,
+        )]);
+
+        assert!(confirmation.is_ok());
+
+        let confirmation = ledger.post(&[
+            entry(LedgerAccount::Available, LedgerAccount::Held, dec!(1)),
+            entry(LedgerAccount::External, LedgerAccount::Available, dec!(1)),
+        ]);
+
+        assert_eq!(
+            confirmation,
+            Err(EngineError::BalanceOverflow(TransactionId(1)))
+        );
+        assert_eq!(ledger.take_posted().len(), 1);
+        assert_eq!(
+            ledger.balance(&Asset::default(), LedgerAccount::Held),
+            Decimal::ZERO
+        );
+    }
+}
@@ -1,3 +1,16 @@
+pub mod ack;
+pub mod builder;
+mod command;
 pub mod engine;
+pub mod fees;
+pub mod history;
+pub mod ledger;
+pub mod policy;
+pub mod query;
+pub mod registry;
+pub mod snapshot;
+pub mod supervisor;
+mod transfer;
+pub mod wal;
 pub mod wallet;
 mod worker;
@@ -0,0 +1,78 @@
+use crate::errors::{EngineError, EngineResult};
+use crate::model::trade::{Timestamp, TransactionId};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+/// Seconds a timestamp of a transaction can be ahead of the system clock.
+pub const MAX_CLOCK_SKEW: u64 = 5 * 60;
+
+/// Rules applied by every [`AccountWallet`](crate::core::wallet::AccountWallet) of the engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalletPolicy {
+    /// Whether a transaction with a resolved dispute can be disputed again.
+    pub allow_redispute: bool,
+    /// Maximal number of decimal places of an amount.
+    pub precision: u32,
+    /// How long a deposit or withdrawal can be disputed, forever without one.
+    pub dispute_window: Option<DisputeWindow>,
+}
+
+impl WalletPolicy {
+    /// Amounts have to be positive and fit in the precision.
+    pub fn validate_amount(&self, id: TransactionId, amount: Decimal) -> EngineResult<Decimal> {
+        if amount.is_sign_negative() || amount.is_zero() {
+            Err(EngineError::NegativeAmount(id))
+        } else if amount.scale() > self.precision {
+            Err(EngineError::InvalidPrecision(id))
+        } else {
+            Ok(amount)
+        }
+    }
+
+    /// Rejects a timestamp ahead of `now` by more than `MAX_CLOCK_SKEW`,
+    /// once accepted it would age out every transaction of the client at once.
+    pub fn check_timestamp(
+        &self,
+        id: TransactionId,
+        timestamp: Timestamp,
+        now: Timestamp,
+    ) -> EngineResult<()> {
+        if timestamp.0 > now.0.saturating_add(MAX_CLOCK_SKEW) {
+            Err(EngineError::FutureTimestamp(id))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for WalletPolicy {
+    fn default() -> Self {
+        Self {
+            allow_redispute: false,
+            precision: 4,
+            dispute_window: None,
+        }
+    }
+}
+
+/// Age after which a transaction can no longer be disputed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisputeWindow {
+    /// Seconds between the transaction and the latest timestamp seen for the client.
+    /// Deposits and withdrawals without a timestamp never age out.
+    Seconds(u64),
+    /// Number of newer deposits and withdrawals of the client.
+    Transactions(u64),
+}
+
+/// What the engine does with a deposit, withdrawal or transfer whose tx id was already seen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Refused with `DuplicateTransaction` and reported as a rejection.
+    #[default]
+    Reject,
+    /// Dropped silently, for sources which deliver the same transaction more than once.
+    Ignore,
+}
@@ -0,0 +1,126 @@
+use crate::core::command::Command;
+use crate::errors::{EngineError, EngineResult};
+use crate::model::account::Account;
+use crate::model::asset::Asset;
+use crate::model::client::ClientId;
+use crate::model::report::Report;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use tokio::sync::{mpsc, oneshot};
+
+/// Channels of the running workers and the shards which have failed, kept up to date
+/// by the engine as workers start, stop and fail.
+#[derive(Default)]
+pub(crate) struct Routes {
+    workers: HashMap<usize, mpsc::Sender<Command>>,
+    failed: HashSet<usize>,
+}
+
+impl Routes {
+    pub(crate) fn insert(&mut self, id: usize, worker: mpsc::Sender<Command>) {
+        self.workers.insert(id, worker);
+    }
+
+    pub(crate) fn remove(&mut self, id: usize) {
+        self.workers.remove(&id);
+    }
+
+    pub(crate) fn fail(&mut self, id: usize) {
+        self.failed.insert(id);
+    }
+}
+
+/// Read-only handle on the engine, queries go straight to the workers so they don't
+/// wait for the caller which owns the engine. Cheap to clone.
+#[derive(Clone)]
+pub struct EngineQueries {
+    workers_size: u16,
+    routes: Arc<RwLock<Routes>>,
+}
+
+impl EngineQueries {
+    pub(crate) fn new(workers_size: u16, routes: Arc<RwLock<Routes>>) -> EngineQueries {
+        EngineQueries {
+            workers_size,
+            routes,
+        }
+    }
+
+    /// Balances of a client in the given asset, once the operations queued before
+    /// the query have been applied.
+    pub async fn account_in(
+        &self,
+        client: ClientId,
+        asset: Asset,
+    ) -> EngineResult<Option<Account>> {
+        let id = (client.0 % self.workers_size) as usize;
+
+        let Some(worker) = self.route(id)? else {
+            return Ok(None);
+        };
+
+        let (reply, response) = oneshot::channel();
+
+        worker
+            .send(Command::Account {
+                client,
+                asset,
+                reply,
+            })
+            .await
+            .map_err(|_| self.worker_failed(id))?;
+
+        response.await.map_err(|_| self.worker_failed(id))
+    }
+
+    /// Current balances of every worker. Unlike `PaymentEngine::snapshot` the fees
+    /// charged since the last submission are not yet credited to the house account.
+    pub async fn report(&self) -> EngineResult<Report> {
+        let workers: Vec<(usize, mpsc::Sender<Command>)> = {
+            let routes = self.read()?;
+
+            if let Some(id) = routes.failed.iter().min() {
+                return Err(self.worker_failed(*id));
+            }
+
+            routes
+                .workers
+                .iter()
+                .map(|(id, worker)| (*id, worker.clone()))
+                .collect()
+        };
+
+        let mut accounts = vec![];
+
+        for (id, worker) in workers {
+            let (reply, response) = oneshot::channel();
+
+            worker
+                .send(Command::Balances { reply })
+                .await
+                .map_err(|_| self.worker_failed(id))?;
+
+            accounts.extend(response.await.map_err(|_| self.worker_failed(id))?);
+        }
+
+        Ok(Report::new(accounts))
+    }
+
+    fn route(&self, id: usize) -> EngineResult<Option<mpsc::Sender<Command>>> {
+        let routes = self.read()?;
+
+        if routes.failed.contains(&id) {
+            return Err(self.worker_failed(id));
+        }
+
+        Ok(routes.workers.get(&id).cloned())
+    }
+
+    fn read(&self) -> EngineResult<RwLockReadGuard<'_, Routes>> {
+        self.routes.read().map_err(|_| EngineError::InternalError())
+    }
+
+    fn worker_failed(&self, id: usize) -> EngineError {
+        EngineError::WorkerFailed(id, self.workers_size)
+    }
+}
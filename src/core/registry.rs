@@ -0,0 +1,227 @@
+use crate::model::trade::TransactionId;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+const PAGE_BITS: u32 = 16;
+const PAGE_WORDS: usize = (1 << PAGE_BITS) / u64::BITS as usize;
+const PAGES: usize = 1 << (u32::BITS - PAGE_BITS);
+
+type Page = Box<[u64; PAGE_WORDS]>;
+
+/// Bitset of every transaction id seen by the engine.
+///
+/// Pages of 8 KiB are allocated lazily, so memory grows with the spread of ids
+/// and never exceeds 512 MiB for the whole `u32` id space.
+/// Serialized as a list of inclusive id ranges.
+#[derive(Clone)]
+pub struct TransactionRegistry {
+    pages: Vec<Option<Page>>,
+}
+
+impl Default for TransactionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TransactionRegistry {
+    pub fn new() -> Self {
+        Self {
+            pages: vec![None; PAGES],
+        }
+    }
+
+    pub fn contains(&self, id: TransactionId) -> bool {
+        let (page, word, bit) = locate(id);
+
+        self.pages[page]
+            .as_ref()
+            .is_some_and(|words| words[word] & bit != 0)
+    }
+
+    /// Registers the id and returns `false` if it was already registered.
+    pub fn insert(&mut self, id: TransactionId) -> bool {
+        let (page, word, bit) = locate(id);
+
+        let words = self.pages[page].get_or_insert_with(|| Box::new([0; PAGE_WORDS]));
+
+        let seen = words[word] & bit != 0;
+        words[word] |= bit;
+
+        !seen
+    }
+
+    fn ranges(&self) -> Vec<(u32, u32)> {
+        let mut ranges: Vec<(u32, u32)> = vec![];
+
+        for (page, words) in self.pages.iter().enumerate() {
+            let Some(words) = words else { continue };
+
+            for (word, bits) in words.iter().enumerate().filter(|(_, bits)| **bits != 0) {
+                for bit in (0..u64::BITS).filter(|bit| bits & (1 << bit) != 0) {
+                    let id = ((page << PAGE_BITS) + word * u64::BITS as usize) as u32 + bit;
+
+                    match ranges.last_mut() {
+                        Some((_, end)) if *end + 1 == id => *end = id,
+                        _ => ranges.push((id, id)),
+                    }
+                }
+            }
+        }
+
+        ranges
+    }
+}
+
+impl Serialize for TransactionRegistry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.ranges().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TransactionRegistry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut registry = TransactionRegistry::new();
+
+        for (start, end) in Vec::<(u32, u32)>::deserialize(deserializer)? {
+            for id in start..=end {
+                registry.insert(TransactionId(id));
+            }
+        }
+
+        Ok(registry)
+    }
+}
+
+/// Set of transaction ids kept as sorted, inclusive ranges. Small as long as the ids
+/// mostly follow each other, a wallet keeps the ids of its transactions which aged out in it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TransactionRanges {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl TransactionRanges {
+    pub fn contains(&self, id: TransactionId) -> bool {
+        let index = self.ranges.partition_point(|(start, _)| *start <= id.0);
+
+        index > 0 && self.ranges[index - 1].1 >= id.0
+    }
+
+    /// Adds the id, merging it with the ranges next to it.
+    pub fn insert(&mut self, id: TransactionId) {
+        let id = id.0;
+        let index = self.ranges.partition_point(|(start, _)| *start <= id);
+        let next = self.ranges.get(index).map(|(start, _)| *start);
+
+        if index > 0 {
+            let end = self.ranges[index - 1].1;
+
+            if end >= id {
+                return;
+            }
+
+            if end + 1 == id {
+                match next {
+                    Some(start) if start == id + 1 => {
+                        self.ranges[index - 1].1 = self.ranges[index].1;
+                        self.ranges.remove(index);
+                    }
+                    _ => self.ranges[index - 1].1 = id,
+                }
+
+                return;
+            }
+        }
+
+        match next {
+            Some(start) if id.checked_add(1) == Some(start) => self.ranges[index].0 = id,
+            _ => self.ranges.insert(index, (id, id)),
+        }
+    }
+}
+
+fn locate(id: TransactionId) -> (usize, usize, u64) {
+    let page = (id.0 >> PAGE_BITS) as usize;
+    let offset = id.0 as usize & ((1 << PAGE_BITS) - 1);
+
+    (
+        page,
+        offset / u64::BITS as usize,
+        1 << (offset % u64::BITS as usize),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::registry::{TransactionRanges, TransactionRegistry};
+    use crate::model::trade::TransactionId;
+
+    #[test]
+    fn test_insert_new_transaction() {
+        let mut registry = TransactionRegistry::new();
+
+        assert!(registry.insert(TransactionId(1)));
+        assert!(registry.contains(TransactionId(1)));
+        assert!(!registry.contains(TransactionId(2)));
+    }
+
+    #[test]
+    fn test_insert_duplicated_transaction() {
+        let mut registry = TransactionRegistry::new();
+
+        assert!(registry.insert(TransactionId(1)));
+        assert!(!registry.insert(TransactionId(1)));
+    }
+
+    #[test]
+    fn test_insert_boundary_transactions() {
+        let mut registry = TransactionRegistry::new();
+
+        assert!(registry.insert(TransactionId(0)));
+        assert!(registry.insert(TransactionId(u32::MAX)));
+        assert!(registry.contains(TransactionId(0)));
+        assert!(registry.contains(TransactionId(u32::MAX)));
+        assert!(!registry.contains(TransactionId(u32::MAX - 1)));
+    }
+
+    #[test]
+    fn test_serialize_as_ranges() -> anyhow::Result<()> {
+        let mut registry = TransactionRegistry::new();
+
+        for id in [1, 2, 3, 5, 65535, 65536, u32::MAX] {
+            registry.insert(TransactionId(id));
+        }
+
+        let json = serde_json::to_string(&registry)?;
+
+        assert_eq!(json, "[[1,3],[5,5],[65535,65536],[4294967295,4294967295]]");
+
+        let restored: TransactionRegistry = serde_json::from_str(&json)?;
+
+        assert!(restored.contains(TransactionId(2)));
+        assert!(restored.contains(TransactionId(65536)));
+        assert!(!restored.contains(TransactionId(4)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_adjacent_ranges() -> anyhow::Result<()> {
+        let mut ranges = TransactionRanges::default();
+
+        for id in [5, 1, 3, 2, 7, 4, u32::MAX] {
+            ranges.insert(TransactionId(id));
+        }
+
+        assert_eq!(
+            serde_json::to_string(&ranges)?,
+            "[[1,5],[7,7],[4294967295,4294967295]]"
+        );
+        assert!(ranges.contains(TransactionId(4)));
+        assert!(ranges.contains(TransactionId(u32::MAX)));
+        assert!(!ranges.contains(TransactionId(6)));
+        assert!(!ranges.contains(TransactionId(0)));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use crate::core::registry::TransactionRegistry;
+use crate::core::wallet::AccountWallet;
+use crate::errors::{EngineError, EngineResult, ErrorSource};
+use crate::model::admin::AuditEntry;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+
+/// Full engine state, independent of the workers pool size.
+#[derive(Serialize, Deserialize)]
+pub struct EngineSnapshot<'a> {
+    pub wallets: Vec<AccountWallet>,
+    pub registry: Cow<'a, TransactionRegistry>,
+    pub audit: Cow<'a, [AuditEntry]>,
+    /// Sequence of the last write-ahead log entry included in the snapshot.
+    #[serde(default)]
+    pub wal_sequence: u64,
+}
+
+impl EngineSnapshot<'_> {
+    pub fn load(path: &str) -> EngineResult<EngineSnapshot<'static>> {
+        let reader = BufReader::new(File::open(path)?);
+
+        serde_json::from_reader(reader).map_err(snapshot_error)
+    }
+
+    /// Writes to a temporary file first, so a crash never leaves a partial snapshot behind.
+    pub fn save(&self, path: &str) -> EngineResult<()> {
+        let temporary = format!("{}.tmp", path);
+
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        serde_json::to_writer(&mut writer, self).map_err(snapshot_error)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+
+        fs::rename(temporary, path)?;
+
+        Ok(())
+    }
+}
+
+/// Io failures keep their own variants, anything else means the snapshot itself is invalid.
+fn snapshot_error(error: serde_json::Error) -> EngineError {
+    match error.io_error_kind() {
+        Some(_) => EngineError::from(error),
+        None => EngineError::Snapshot(ErrorSource::new(error)),
+    }
+}
@@ -0,0 +1,226 @@
+use crate::core::command::{Operation, WalletTransaction};
+use crate::core::fees::FeePolicy;
+use crate::core::history::Spill;
+use crate::core::policy::WalletPolicy;
+use crate::core::wallet::AccountWallet;
+use crate::core::worker::EngineWorker;
+use crate::model::client::ClientId;
+use crate::model::rejection::Origin;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::sync::oneshot;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::task;
+use tracing::warn;
+
+type Wallets = HashMap<ClientId, AccountWallet>;
+
+/// What the engine does once a worker has died, e.g. after a panic.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Supervision {
+    /// The shard stays down, its clients are rejected with `WorkerFailed`.
+    /// Other shards keep running.
+    #[default]
+    Isolate,
+    /// The worker is rebuilt by replaying the operations it handled since the last restore
+    /// or checkpoint. The operation which failed and the ones queued behind it are rejected.
+    Restart,
+}
+
+/// Number of operations a journal keeps before the handled ones are folded into its seed.
+pub(crate) const JOURNAL_CAPACITY: usize = 10_000;
+
+/// Operations sent to a worker since its seed state, kept only with `Supervision::Restart`.
+pub(crate) struct Journal {
+    id: usize,
+    policy: WalletPolicy,
+    fees: Arc<dyn FeePolicy>,
+    spill: Option<Spill>,
+    seed: Arc<Wallets>,
+    operations: Vec<(Operation, Option<Origin>)>,
+    handled: Arc<AtomicUsize>,
+    compaction: Option<Compaction>,
+}
+
+/// New seed being replayed on the blocking thread pool, it includes the first
+/// `operations` of the journal.
+struct Compaction {
+    operations: usize,
+    seed: oneshot::Receiver<Wallets>,
+}
+
+impl Journal {
+    pub(crate) fn new(
+        id: usize,
+        policy: WalletPolicy,
+        fees: Arc<dyn FeePolicy>,
+        spill: Option<Spill>,
+        seed: Wallets,
+        handled: Arc<AtomicUsize>,
+    ) -> Self {
+        Self {
+            id,
+            policy,
+            fees,
+            spill,
+            seed: Arc::new(seed),
+            operations: vec![],
+            handled,
+            compaction: None,
+        }
+    }
+
+    pub(crate) fn record(&mut self, operation: Operation, origin: Option<Origin>) {
+        self.operations.push((operation, origin));
+
+        self.finish_compaction();
+
+        if self.operations.len() >= JOURNAL_CAPACITY && self.compaction.is_none() {
+            self.compact();
+        }
+    }
+
+    /// Replays the handled operations on top of the seed on the blocking thread pool,
+    /// so the journal stays bounded without checkpoints and the engine is not held up.
+    /// The journal keeps its operations until the new seed is ready.
+    fn compact(&mut self) {
+        let handled = self.count_handled();
+
+        let operations: Vec<Operation> = self.operations[..handled]
+            .iter()
+            .map(|(operation, _)| operation.clone())
+            .collect();
+
+        let worker = self.worker();
+        let (sender, seed) = oneshot::channel();
+
+        task::spawn_blocking(move || {
+            let _ = sender.send(replay(worker, operations).accounts());
+        });
+
+        self.compaction = Some(Compaction {
+            operations: handled,
+            seed,
+        });
+    }
+
+    /// Swaps in the seed of a finished compaction and drops the operations it includes.
+    fn finish_compaction(&mut self) {
+        let Some(compaction) = &mut self.compaction else {
+            return;
+        };
+
+        match compaction.seed.try_recv() {
+            Ok(seed) => {
+                let handled = compaction.operations;
+
+                self.seed = Arc::new(seed);
+                self.operations.drain(..handled);
+                self.handled.fetch_sub(handled, Ordering::AcqRel);
+                self.compaction = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Closed) => {
+                warn!("Journal of worker {} has not been compacted", self.id);
+                self.compaction = None;
+            }
+        }
+    }
+
+    fn worker(&self) -> EngineWorker {
+        EngineWorker::new(
+            self.id,
+            self.policy,
+            self.fees.clone(),
+            self.spill.clone(),
+            self.seed.as_ref().clone(),
+        )
+    }
+
+    fn count_handled(&self) -> usize {
+        self.handled
+            .load(Ordering::Acquire)
+            .min(self.operations.len())
+    }
+
+    /// Starts over from the given state, all recorded operations are part of it.
+    /// A compaction still running is dropped.
+    pub(crate) fn reset(&mut self, seed: Wallets) {
+        self.seed = Arc::new(seed);
+        self.operations.clear();
+        self.handled.store(0, Ordering::Release);
+        self.compaction = None;
+    }
+
+    /// Replays the handled operations on top of the seed. Operations the worker has not
+    /// handled are removed and returned, the first one is the operation it failed on.
+    pub(crate) fn rebuild(&mut self) -> (Wallets, Vec<(Operation, Option<Origin>)>) {
+        let handled = self.count_handled();
+
+        let operations = self.operations[..handled]
+            .iter()
+            .map(|(operation, _)| operation.clone())
+            .collect();
+
+        let worker = replay(self.worker(), operations);
+
+        let unhandled = self.operations.split_off(handled);
+
+        (worker.accounts(), unhandled)
+    }
+
+    pub(crate) fn handled(&self) -> Arc<AtomicUsize> {
+        self.handled.clone()
+    }
+}
+
+/// Operations sent to a worker and not yet handled, kept with `Supervision::Isolate`
+/// so the client transactions lost with a failed worker can be reported.
+pub(crate) struct InFlight {
+    operations: VecDeque<Option<(WalletTransaction, Option<Origin>)>>,
+    handled: Arc<AtomicUsize>,
+}
+
+impl InFlight {
+    pub(crate) fn new(handled: Arc<AtomicUsize>) -> Self {
+        Self {
+            operations: VecDeque::new(),
+            handled,
+        }
+    }
+
+    /// Internal operations are only counted, the worker answers them to the engine.
+    pub(crate) fn record(&mut self, operation: Operation, origin: Option<Origin>) {
+        let transaction = match operation {
+            Operation::Apply(tx) => Some((tx, origin)),
+            _ => None,
+        };
+
+        self.operations.push_back(transaction);
+        self.trim();
+    }
+
+    /// Client transactions the worker has not handled, the first one may be the
+    /// transaction it failed on.
+    pub(crate) fn lost(mut self) -> Vec<(WalletTransaction, Option<Origin>)> {
+        self.trim();
+        self.operations.into_iter().flatten().collect()
+    }
+
+    fn trim(&mut self) {
+        let handled = self.handled.swap(0, Ordering::AcqRel);
+
+        self.operations.drain(..handled.min(self.operations.len()));
+    }
+}
+
+fn replay(mut worker: EngineWorker, operations: Vec<Operation>) -> EngineWorker {
+    for operation in operations {
+        let _ = worker.handle(operation);
+    }
+
+    worker
+}
@@ -0,0 +1,81 @@
+use crate::core::command::{Operation, Reply, Transfer};
+use crate::errors::EngineResult;
+use crate::model::client::ClientId;
+use crate::model::rejection::Origin;
+use crate::model::statement::Step;
+use rust_decimal::Decimal;
+use tokio::sync::oneshot;
+use tracing::warn;
+
+/// Leg of a transfer the engine waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Reserving,
+    Crediting,
+    Committing,
+    Releasing,
+}
+
+/// Transfer whose legs are still answered by the workers. The engine moves it to
+/// the next leg once the current one is answered, without holding up other clients.
+pub(crate) struct PendingTransfer {
+    pub(crate) transfer: Transfer,
+    pub(crate) fee: Decimal,
+    pub(crate) phase: Phase,
+    pub(crate) response: oneshot::Receiver<EngineResult<()>>,
+    pub(crate) origin: Option<Origin>,
+    pub(crate) reply: Option<Reply>,
+    pub(crate) step: Option<Step>,
+    /// A commit or release lost with its worker is sent once more after the restart.
+    pub(crate) resent: bool,
+}
+
+impl PendingTransfer {
+    /// Operation of the current leg.
+    pub(crate) fn operation(&self) -> Operation {
+        let Transfer {
+            client,
+            to,
+            trade,
+            asset,
+            amount,
+        } = self.transfer.clone();
+
+        match self.phase {
+            Phase::Reserving => Operation::Reserve {
+                client,
+                trade,
+                asset,
+                amount,
+                fee: self.fee,
+            },
+            Phase::Crediting => Operation::Credit {
+                client: to,
+                trade,
+                asset,
+                amount,
+            },
+            Phase::Committing => Operation::Commit { client, trade },
+            Phase::Releasing => Operation::Release { client, trade },
+        }
+    }
+
+    /// Answers the caller of `submit`, only the first outcome is sent.
+    pub(crate) fn answer(&mut self, result: EngineResult<()>) {
+        if let Some(reply) = self.reply.take() {
+            reply.send(result).unwrap_or_else(|_| {
+                warn!("Transaction result has not been received");
+            });
+        }
+    }
+
+    /// Once the commit or release is queued on the sender, later operations of either
+    /// client are applied after the transfer just like with a single worker.
+    pub(crate) fn is_settling(&self) -> bool {
+        matches!(self.phase, Phase::Committing | Phase::Releasing)
+    }
+
+    pub(crate) fn involves(&self, client: ClientId) -> bool {
+        self.transfer.client == client || self.transfer.to == client
+    }
+}
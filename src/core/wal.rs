@@ -0,0 +1,205 @@
+use crate::errors::{EngineError, EngineResult, ErrorSource};
+use crate::model::admin::AdminCommand;
+use crate::model::trade::Transaction;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::sync::Arc;
+use tokio::task;
+use tracing::{info, warn};
+
+const HEADER_SIZE: usize = 8;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LogEntry {
+    Transaction(Transaction),
+    Admin(AdminCommand),
+}
+
+/// When appended records are synced to disk. Syncing every record is the safest and the
+/// slowest option, the other ones acknowledge transactions a crash of the machine can lose.
+/// Records are written before the acknowledgement in every mode, so they survive a crash of
+/// the process itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WalSync {
+    /// Every record is synced before its transaction is acknowledged.
+    #[default]
+    Always,
+    /// Records are synced once this many of them have been appended since the last sync.
+    Batch(u32),
+    /// Records are synced by checkpoints only, the system flushes them in between.
+    Never,
+}
+
+/// Entries are numbered, so the ones already saved in a snapshot can be told apart.
+#[derive(Serialize, Deserialize)]
+struct Record<'a> {
+    sequence: u64,
+    entry: Cow<'a, LogEntry>,
+}
+
+/// Append-only log of accepted transactions.
+///
+/// Every record is `[length: u32][crc32: u32][json payload]` and is synced to disk
+/// according to the [`WalSync`] policy. Appends run on the blocking thread pool,
+/// so the runtime threads never wait for the disk.
+pub struct WriteAheadLog {
+    file: Arc<File>,
+    sequence: u64,
+    sync: WalSync,
+    unsynced: u32,
+}
+
+impl WriteAheadLog {
+    /// Opens (or creates) the log, reads all complete records and truncates a torn tail.
+    /// Records up to the `checkpoint` sequence are already part of the restored snapshot
+    /// and are skipped.
+    pub fn open(
+        path: &str,
+        checkpoint: u64,
+        sync: WalSync,
+    ) -> EngineResult<(WriteAheadLog, Vec<LogEntry>)> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let mut entries = vec![];
+        let mut offset = 0;
+        let mut sequence = checkpoint;
+        let mut skipped = 0;
+
+        while let Some((record, size)) = read_record(&mut file)? {
+            if record.sequence > checkpoint {
+                entries.push(record.entry.into_owned());
+            } else {
+                skipped += 1;
+            }
+
+            sequence = sequence.max(record.sequence);
+            offset += size;
+        }
+
+        if skipped > 0 {
+            info!(
+                "Skipping {} write-ahead log entries saved in the snapshot",
+                skipped
+            );
+        }
+
+        if file.metadata()?.len() > offset {
+            warn!("Truncating torn write-ahead log tail at {}", offset);
+
+            file.set_len(offset)?;
+            file.sync_all()?;
+        }
+
+        file.seek(SeekFrom::Start(offset))?;
+
+        let wal = WriteAheadLog {
+            file: Arc::new(file),
+            sequence,
+            sync,
+            unsynced: 0,
+        };
+
+        Ok((wal, entries))
+    }
+
+    /// Sequence of the last appended entry.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub async fn append(&mut self, entry: &LogEntry) -> EngineResult<()> {
+        let record = Record {
+            sequence: self.sequence + 1,
+            entry: Cow::Borrowed(entry),
+        };
+
+        let payload =
+            serde_json::to_vec(&record).map_err(|e| EngineError::Wal(ErrorSource::new(e)))?;
+
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + payload.len());
+        bytes.extend((payload.len() as u32).to_le_bytes());
+        bytes.extend(crc32fast::hash(&payload).to_le_bytes());
+        bytes.extend(payload);
+
+        let sync = match self.sync {
+            WalSync::Always => true,
+            WalSync::Batch(records) => self.unsynced + 1 >= records,
+            WalSync::Never => false,
+        };
+
+        let file = self.file.clone();
+
+        task::spawn_blocking(move || -> io::Result<()> {
+            (&*file).write_all(&bytes)?;
+
+            if sync {
+                file.sync_data()?;
+            }
+
+            Ok(())
+        })
+        .await
+        .map_err(|_| EngineError::InternalError())??;
+
+        self.unsynced = if sync { 0 } else { self.unsynced + 1 };
+        self.sequence += 1;
+
+        Ok(())
+    }
+
+    /// Drops all records, used once their effects are saved in a checkpoint.
+    /// Numbering continues, so a snapshot saved before the reset still skips the new entries.
+    pub fn reset(&mut self) -> EngineResult<()> {
+        self.file.set_len(0)?;
+        (&*self.file).seek(SeekFrom::Start(0))?;
+        self.file.sync_all()?;
+
+        self.unsynced = 0;
+
+        Ok(())
+    }
+}
+
+fn read_record(file: &mut File) -> EngineResult<Option<(Record<'static>, u64)>> {
+    let mut header = [0; HEADER_SIZE];
+
+    if !read_exact(file, &mut header)? {
+        return Ok(None);
+    }
+
+    let length = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let checksum = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+    let remaining = file.metadata()?.len() - file.stream_position()?;
+
+    if length as u64 > remaining {
+        return Ok(None);
+    }
+
+    let mut payload = vec![0; length];
+
+    if !read_exact(file, &mut payload)? || crc32fast::hash(&payload) != checksum {
+        return Ok(None);
+    }
+
+    let record =
+        serde_json::from_slice(&payload).map_err(|e| EngineError::Wal(ErrorSource::new(e)))?;
+
+    Ok(Some((record, (HEADER_SIZE + length) as u64)))
+}
+
+fn read_exact(file: &mut File, buffer: &mut [u8]) -> EngineResult<bool> {
+    match file.read_exact(buffer) {
+        Ok(()) => Ok(true),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
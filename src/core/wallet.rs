@@ -1,166 +1,749 @@
+use crate::core::history::{Spill, TradeHistory};
+use crate::core::ledger::Ledger;
+use crate::core::policy::{DisputeWindow, WalletPolicy};
+use crate::core::registry::TransactionRanges;
 use crate::errors::{EngineError, EngineResult};
-use crate::model::account::Account;
+use crate::model::account::{Account, LockReason};
+use crate::model::asset::Asset;
 use crate::model::client::ClientId;
-use crate::model::trade::TransactionId;
+use crate::model::ledger::{EntryKind, JournalEntry, LedgerAccount};
+use crate::model::trade::{Timestamp, TransactionId};
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum TradeKind {
+    Deposit,
+    Withdrawal,
+}
+
+/// Lifecycle of a processed deposit or withdrawal:
+/// `Processed -> Disputed -> Resolved | ChargedBack`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeState {
+    Processed,
+    Disputed,
+    Resolved,
+    ChargedBack,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct TradeRecord {
+    kind: TradeKind,
+    asset: Asset,
+    amount: Decimal,
+    state: TradeState,
+    #[serde(default)]
+    age: Age,
+}
 
+/// When a transaction was recorded, compared against the dispute window.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct Age {
+    /// Number of deposits and withdrawals of the client up to this one.
+    sequence: u64,
+    /// Timestamp of the transaction, moved up to the latest one seen for the client,
+    /// so an older transaction never outlives a newer one. `None` when it has none.
+    timestamp: Option<Timestamp>,
+}
+
+/// Balances of a single client, one per asset. A lock freezes all of them.
+/// Every change of a balance is posted to the ledger as balanced journal entries.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct AccountWallet {
     client: ClientId,
-    available: Decimal,
-    held: Decimal,
-    total: Decimal,
-    locked: bool,
-    deposits: HashMap<TransactionId, Decimal>,
-    disputes: HashMap<TransactionId, Decimal>,
+    #[serde(skip)]
+    policy: WalletPolicy,
+    ledger: Ledger,
+    lock: Option<LockReason>,
+    trades: TradeHistory,
+    reservations: HashMap<TransactionId, Reservation>,
+    #[serde(default)]
+    sequence: u64,
+    #[serde(default)]
+    clock: Option<Timestamp>,
+    /// Transactions which can still age out, oldest first.
+    #[serde(default)]
+    aging: VecDeque<(TransactionId, Age)>,
+    /// Transactions which aged out and were removed from the history.
+    #[serde(default)]
+    expired: TransactionRanges,
 }
 
-impl From<AccountWallet> for Account {
-    fn from(wallet: AccountWallet) -> Self {
-        Account {
-            client: wallet.client,
-            available: wallet.available,
-            held: wallet.held,
-            total: wallet.total,
-            locked: wallet.locked,
-        }
-    }
+#[derive(Clone, Serialize, Deserialize)]
+struct Reservation {
+    asset: Asset,
+    amount: Decimal,
+    fee: Decimal,
 }
 
 impl AccountWallet {
     pub fn new(client_id: ClientId) -> Self {
+        Self::with_policy(client_id, WalletPolicy::default())
+    }
+
+    pub fn with_policy(client_id: ClientId, policy: WalletPolicy) -> Self {
         Self {
             client: client_id,
-            available: Decimal::ZERO,
-            held: Decimal::ZERO,
-            total: Decimal::ZERO,
-            locked: false,
-            deposits: HashMap::new(),
-            disputes: HashMap::new(),
+            policy,
+            ledger: Ledger::default(),
+            lock: None,
+            trades: TradeHistory::new(client_id),
+            reservations: HashMap::new(),
+            sequence: 0,
+            clock: None,
+            aging: VecDeque::new(),
+            expired: TransactionRanges::default(),
+        }
+    }
+
+    pub fn client(&self) -> ClientId {
+        self.client
+    }
+
+    /// One row per asset, a wallet without any balance is reported in the default asset.
+    pub fn accounts(&self) -> Vec<Account> {
+        let assets = self.ledger.assets();
+
+        if assets.is_empty() {
+            return vec![self.to_account(&Asset::default())];
+        }
+
+        assets.iter().map(|asset| self.to_account(asset)).collect()
+    }
+
+    pub fn account(&self, asset: &Asset) -> Option<Account> {
+        self.accounts()
+            .into_iter()
+            .find(|account| account.asset == *asset)
+    }
+
+    /// Total counts the funds reserved for outgoing transfers until they are committed.
+    fn to_account(&self, asset: &Asset) -> Account {
+        let available = self.available(asset);
+        let held = self.held(asset);
+        let reserved = self.ledger.balance(asset, LedgerAccount::Reserved);
+
+        Account {
+            client: self.client,
+            asset: asset.clone(),
+            available,
+            held,
+            total: available + held + reserved,
+            fees: self.ledger.balance(asset, LedgerAccount::Fees),
+            locked: self.lock.is_some(),
+            lock_reason: self.lock.clone(),
+        }
+    }
+
+    /// Entries posted for the client since the last call, in order.
+    pub fn take_entries(&mut self) -> Vec<JournalEntry> {
+        self.ledger.take_posted()
+    }
+
+    pub(crate) fn set_policy(&mut self, policy: WalletPolicy) {
+        self.policy = policy;
+    }
+
+    /// Paged out transactions are read from the store, `None` as well when it cannot be read.
+    pub fn state(&self, id: TransactionId) -> Option<TradeState> {
+        self.trades.peek(id).ok().flatten().map(|trade| trade.state)
+    }
+
+    /// Asset and amount of a recorded deposit or withdrawal.
+    pub(crate) fn trade(&self, id: TransactionId) -> Option<(Asset, Decimal)> {
+        self.trades
+            .peek(id)
+            .ok()
+            .flatten()
+            .map(|trade| (trade.asset, trade.amount))
+    }
+
+    /// Older transactions are paged out to the store once the wallet holds more than its capacity.
+    pub(crate) fn set_spill(&mut self, spill: Option<Spill>) {
+        self.trades.set_spill(self.client, spill);
+    }
+
+    /// Moves the clock of the wallet forward, transactions older than the dispute window are evicted.
+    /// Called once a transaction with the timestamp has been accepted.
+    pub fn advance(&mut self, now: Timestamp) {
+        if self.clock.is_some_and(|clock| clock >= now) {
+            return;
+        }
+
+        self.clock = Some(now);
+        self.sweep();
+    }
+
+    /// Asset and amount of an outgoing transfer waiting to be committed or released.
+    pub(crate) fn reservation(&self, id: TransactionId) -> Option<(Asset, Decimal)> {
+        self.reservations
+            .get(&id)
+            .map(|reservation| (reservation.asset.clone(), reservation.amount))
+    }
+
+    fn available(&self, asset: &Asset) -> Decimal {
+        self.ledger.balance(asset, LedgerAccount::Available)
+    }
+
+    fn held(&self, asset: &Asset) -> Decimal {
+        self.ledger.balance(asset, LedgerAccount::Held)
+    }
+
+    fn entry(
+        &self,
+        id: TransactionId,
+        kind: EntryKind,
+        asset: &Asset,
+        (debit, credit): (LedgerAccount, LedgerAccount),
+        amount: Decimal,
+    ) -> JournalEntry {
+        JournalEntry {
+            client: self.client,
+            tx: id,
+            kind,
+            asset: asset.clone(),
+            debit,
+            credit,
+            amount,
         }
     }
 
+    /// Entries of a single operation are posted together, none of them if a balance overflows.
+    fn post(&mut self, entries: &[JournalEntry]) -> EngineResult<()> {
+        self.ledger.post(entries)
+    }
+
     fn check_frozen(&self) -> EngineResult<()> {
-        if self.locked {
+        if self.lock.is_some() {
             Err(EngineError::FrozenAccount(self.client))
         } else {
             Ok(())
         }
     }
 
-    fn check_available_founds(&self, id: TransactionId, amount: &Decimal) -> EngineResult<()> {
-        if self.available < *amount {
+    fn check_available_founds(
+        &self,
+        id: TransactionId,
+        asset: &Asset,
+        amount: &Decimal,
+    ) -> EngineResult<()> {
+        if self.available(asset) < *amount {
             Err(EngineError::NotEnoughMany(id))
         } else {
             Ok(())
         }
     }
 
-    fn check_held_founds(&self, id: TransactionId, amount: &Decimal) -> EngineResult<()> {
-        if self.held < *amount {
+    fn check_held_founds(
+        &self,
+        id: TransactionId,
+        asset: &Asset,
+        amount: &Decimal,
+    ) -> EngineResult<()> {
+        if self.held(asset) < *amount {
             Err(EngineError::NotEnoughMany(id))
         } else {
             Ok(())
         }
     }
 
-    fn find_deposit(&self, id: TransactionId) -> EngineResult<Decimal> {
-        self.deposits
-            .get(&id)
-            .cloned()
-            .ok_or(EngineError::TransactionNotFound(id))
+    fn validate_amount(&self, id: TransactionId, amount: Decimal) -> EngineResult<Decimal> {
+        self.policy.validate_amount(id, amount)
     }
 
-    fn find_dispute(&self, id: TransactionId) -> EngineResult<Decimal> {
-        self.disputes
-            .get(&id)
-            .cloned()
-            .ok_or(EngineError::TransactionNotFound(id))
+    /// A transaction which aged out is told apart from one which was never recorded.
+    fn find_trade(&mut self, id: TransactionId) -> EngineResult<TradeRecord> {
+        match self.trades.get(id)? {
+            Some(trade) => Ok(trade.clone()),
+            None if self.expired.contains(id) => Err(EngineError::DisputeWindowExpired(id)),
+            None => Err(EngineError::TransactionNotFound(id)),
+        }
+    }
+
+    fn find_disputable(
+        &mut self,
+        id: TransactionId,
+        timestamp: Option<Timestamp>,
+    ) -> EngineResult<(TradeKind, Asset, Decimal)> {
+        let trade = self.find_trade(id)?;
+
+        if self.is_expired(&trade.age, timestamp) {
+            return Err(EngineError::DisputeWindowExpired(id));
+        }
+
+        match trade.state {
+            TradeState::Processed => Ok((trade.kind, trade.asset.clone(), trade.amount)),
+            TradeState::Resolved if self.policy.allow_redispute => {
+                Ok((trade.kind, trade.asset.clone(), trade.amount))
+            }
+            TradeState::Resolved => Err(EngineError::AlreadyResolved(id)),
+            TradeState::Disputed => Err(EngineError::AlreadyDisputed(id)),
+            TradeState::ChargedBack => Err(EngineError::AlreadyChargedBack(id)),
+        }
+    }
+
+    fn find_dispute(&mut self, id: TransactionId) -> EngineResult<(TradeKind, Asset, Decimal)> {
+        let trade = self.find_trade(id)?;
+
+        match trade.state {
+            TradeState::Disputed => Ok((trade.kind, trade.asset.clone(), trade.amount)),
+            TradeState::Processed => Err(EngineError::NotDisputed(id)),
+            TradeState::Resolved => Err(EngineError::AlreadyResolved(id)),
+            TradeState::ChargedBack => Err(EngineError::AlreadyChargedBack(id)),
+        }
+    }
+
+    fn record(
+        &mut self,
+        id: TransactionId,
+        kind: TradeKind,
+        asset: Asset,
+        amount: Decimal,
+        timestamp: Option<Timestamp>,
+    ) {
+        self.sequence += 1;
+
+        let age = Age {
+            sequence: self.sequence,
+            timestamp: timestamp
+                .map(|timestamp| self.clock.map_or(timestamp, |clock| clock.max(timestamp))),
+        };
+
+        let trade = TradeRecord {
+            kind,
+            asset,
+            amount,
+            state: TradeState::Processed,
+            age,
+        };
+
+        self.trades.insert(id, trade);
+
+        if self.can_age(&age) {
+            self.aging.push_back((id, age));
+            self.sweep();
+        }
+
+        self.trades.trim();
     }
 
-    pub fn deposit(&mut self, id: TransactionId, amount: Decimal) -> EngineResult<()> {
-        let amount = validate_amount(id, amount)?;
+    /// A transaction which aged out while it was disputed is evicted once the dispute is closed.
+    fn transition(&mut self, id: TransactionId, state: TradeState) {
+        let mut age = None;
+
+        if let Ok(Some(trade)) = self.trades.get(id) {
+            trade.state = state;
+            age = Some(trade.age);
+        }
+
+        if state != TradeState::Disputed && age.is_some_and(|age| self.is_expired(&age, None)) {
+            self.evict(id);
+        }
+
+        self.trades.trim();
+    }
+
+    /// Transactions without a timestamp never age out of a window in seconds.
+    fn can_age(&self, age: &Age) -> bool {
+        match self.policy.dispute_window {
+            None => false,
+            Some(DisputeWindow::Seconds(_)) => age.timestamp.is_some(),
+            Some(DisputeWindow::Transactions(_)) => true,
+        }
+    }
+
+    /// Compared with the clock of the wallet, or with the timestamp of the dispute if it is later.
+    fn is_expired(&self, age: &Age, timestamp: Option<Timestamp>) -> bool {
+        let clock = self.clock.max(timestamp);
+
+        match self.policy.dispute_window {
+            None => false,
+            Some(DisputeWindow::Seconds(window)) => match (clock, age.timestamp) {
+                (Some(now), Some(then)) => now.0.saturating_sub(then.0) > window,
+                _ => false,
+            },
+            Some(DisputeWindow::Transactions(window)) => {
+                self.sequence.saturating_sub(age.sequence) >= window
+            }
+        }
+    }
+
+    /// Evicts the transactions which aged out, a disputed one stays until its dispute is closed.
+    /// Both the sequence and the clock only grow, so the oldest transactions are always in front.
+    fn sweep(&mut self) {
+        while let Some(&(id, age)) = self.aging.front() {
+            if !self.is_expired(&age, None) {
+                return;
+            }
+
+            self.aging.pop_front();
+
+            if self.state(id) != Some(TradeState::Disputed) {
+                self.evict(id);
+            }
+        }
+    }
+
+    fn evict(&mut self, id: TransactionId) {
+        self.expired.insert(id);
+
+        if let Err(error) = self.trades.remove(id) {
+            warn!(
+                "Transaction {} has not been removed from history: {}",
+                id, error
+            );
+        }
+    }
+
+    pub fn deposit(
+        &mut self,
+        id: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+    ) -> EngineResult<()> {
+        self.deposit_with_fee(id, asset, amount, Decimal::ZERO, None)
+    }
+
+    /// The fee is taken from the deposited funds, the balance after the deposit has to cover it.
+    pub fn deposit_with_fee(
+        &mut self,
+        id: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+        fee: Decimal,
+        timestamp: Option<Timestamp>,
+    ) -> EngineResult<()> {
+        let amount = self.validate_amount(id, amount)?;
+
+        self.check_frozen()?;
+
+        if fee > amount {
+            self.check_available_founds(id, &asset, &(fee - amount))?;
+        }
+
+        self.post(&[
+            self.entry(
+                id,
+                EntryKind::Deposit,
+                &asset,
+                (LedgerAccount::External, LedgerAccount::Available),
+                amount,
+            ),
+            self.fee_entry(id, &asset, fee),
+        ])?;
+
+        self.record(id, TradeKind::Deposit, asset, amount, timestamp);
+
+        Ok(())
+    }
+
+    pub fn withdrawal(
+        &mut self,
+        id: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+    ) -> EngineResult<()> {
+        self.withdrawal_with_fee(id, asset, amount, Decimal::ZERO, None)
+    }
+
+    /// Available funds have to cover both the amount and the fee.
+    pub fn withdrawal_with_fee(
+        &mut self,
+        id: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+        fee: Decimal,
+        timestamp: Option<Timestamp>,
+    ) -> EngineResult<()> {
+        let amount = self.validate_amount(id, amount)?;
 
         self.check_frozen()?;
 
-        self.available += amount;
-        self.total += amount;
+        let total = checked_total(id, amount, fee)?;
+        self.check_available_founds(id, &asset, &total)?;
+
+        self.post(&[
+            self.entry(
+                id,
+                EntryKind::Withdrawal,
+                &asset,
+                (LedgerAccount::Available, LedgerAccount::External),
+                amount,
+            ),
+            self.fee_entry(id, &asset, fee),
+        ])?;
 
-        self.deposits.insert(id, amount);
+        self.record(id, TradeKind::Withdrawal, asset, amount, timestamp);
 
         Ok(())
     }
 
-    pub fn withdrawal(&mut self, id: TransactionId, amount: Decimal) -> EngineResult<()> {
-        let amount = validate_amount(id, amount)?;
+    /// Debits a fee as a separate entry, next to the transaction it was charged for.
+    /// Funds are not checked, a fee charged on a chargeback can leave the balance negative.
+    pub(crate) fn charge(
+        &mut self,
+        id: TransactionId,
+        asset: &Asset,
+        fee: Decimal,
+    ) -> EngineResult<()> {
+        self.post(&[self.fee_entry(id, asset, fee)])
+    }
+
+    fn fee_entry(&self, id: TransactionId, asset: &Asset, fee: Decimal) -> JournalEntry {
+        self.entry(
+            id,
+            EntryKind::Fee,
+            asset,
+            (LedgerAccount::Available, LedgerAccount::Fees),
+            fee,
+        )
+    }
 
+    pub fn lock(&mut self, reason: LockReason) -> EngineResult<()> {
         self.check_frozen()?;
-        self.check_available_founds(id, &amount)?;
 
-        self.available -= amount;
-        self.total -= amount;
+        self.lock = Some(reason);
+
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) -> EngineResult<()> {
+        if self.lock.take().is_none() {
+            return Err(EngineError::NotLocked(self.client));
+        }
 
         Ok(())
     }
 
+    /// First phase of an outgoing transfer, moves the amount and the fee out of available funds
+    /// until the transfer is committed or released.
+    pub fn reserve(
+        &mut self,
+        id: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+        fee: Decimal,
+    ) -> EngineResult<()> {
+        let amount = self.validate_amount(id, amount)?;
+
+        self.check_frozen()?;
+
+        let total = checked_total(id, amount, fee)?;
+        self.check_available_founds(id, &asset, &total)?;
+
+        self.post(&[self.entry(
+            id,
+            EntryKind::Reserve,
+            &asset,
+            (LedgerAccount::Available, LedgerAccount::Reserved),
+            total,
+        )])?;
+
+        let reservation = Reservation { asset, amount, fee };
+
+        self.reservations.insert(id, reservation);
+
+        Ok(())
+    }
+
+    /// Returns the asset and the fee charged for the transfer.
+    pub fn commit(&mut self, id: TransactionId) -> EngineResult<(Asset, Decimal)> {
+        let Reservation { asset, amount, fee } = self.find_reservation(id)?;
+
+        self.post(&[
+            self.entry(
+                id,
+                EntryKind::Commit,
+                &asset,
+                (LedgerAccount::Reserved, LedgerAccount::External),
+                amount,
+            ),
+            self.entry(
+                id,
+                EntryKind::Fee,
+                &asset,
+                (LedgerAccount::Reserved, LedgerAccount::Fees),
+                fee,
+            ),
+        ])?;
+
+        self.reservations.remove(&id);
+
+        Ok((asset, fee))
+    }
+
+    pub fn release(&mut self, id: TransactionId) -> EngineResult<()> {
+        let Reservation { asset, amount, fee } = self.find_reservation(id)?;
+
+        self.post(&[self.entry(
+            id,
+            EntryKind::Release,
+            &asset,
+            (LedgerAccount::Reserved, LedgerAccount::Available),
+            amount + fee,
+        )])?;
+
+        self.reservations.remove(&id);
+
+        Ok(())
+    }
+
+    /// Incoming leg of a transfer.
+    pub fn credit(&mut self, id: TransactionId, asset: Asset, amount: Decimal) -> EngineResult<()> {
+        let amount = self.validate_amount(id, amount)?;
+
+        self.check_frozen()?;
+
+        self.post(&[self.entry(
+            id,
+            EntryKind::Credit,
+            &asset,
+            (LedgerAccount::External, LedgerAccount::Available),
+            amount,
+        )])?;
+
+        Ok(())
+    }
+
+    /// Fee charged to another client. It is credited even when the account is locked,
+    /// as the paying client has already been debited.
+    pub fn collect(
+        &mut self,
+        id: TransactionId,
+        asset: Asset,
+        amount: Decimal,
+    ) -> EngineResult<()> {
+        let amount = self.validate_amount(id, amount)?;
+
+        self.post(&[self.entry(
+            id,
+            EntryKind::Credit,
+            &asset,
+            (LedgerAccount::External, LedgerAccount::Available),
+            amount,
+        )])
+    }
+
+    /// The reservation is removed by the caller once its entries are posted.
+    fn find_reservation(&self, id: TransactionId) -> EngineResult<Reservation> {
+        self.reservations
+            .get(&id)
+            .cloned()
+            .ok_or(EngineError::TransactionNotFound(id))
+    }
+
+    /// Disputing a deposit holds the deposited funds, so the client has to still have them.
+    /// Disputing a withdrawal holds the withdrawn amount on top of the current balance,
+    /// as the money may have to be returned to the client.
+    /// Funds are held in the asset of the disputed transaction.
     pub fn dispute(&mut self, id: TransactionId) -> EngineResult<()> {
-        let amount = self.find_deposit(id)?;
+        self.dispute_at(id, None)
+    }
+
+    /// The dispute window is checked against the timestamp of the dispute if it is later
+    /// than the clock of the wallet.
+    pub fn dispute_at(
+        &mut self,
+        id: TransactionId,
+        timestamp: Option<Timestamp>,
+    ) -> EngineResult<()> {
+        let (kind, asset, amount) = self.find_disputable(id, timestamp)?;
 
         self.check_frozen()?;
-        self.check_available_founds(id, &amount)?;
 
-        self.available -= amount;
-        self.held += amount;
+        if kind == TradeKind::Deposit {
+            self.check_available_founds(id, &asset, &amount)?;
+        }
+
+        let source = match kind {
+            TradeKind::Deposit => LedgerAccount::Available,
+            TradeKind::Withdrawal => LedgerAccount::External,
+        };
+
+        self.post(&[self.entry(
+            id,
+            EntryKind::Dispute,
+            &asset,
+            (source, LedgerAccount::Held),
+            amount,
+        )])?;
 
-        self.disputes.insert(id, amount);
+        self.transition(id, TradeState::Disputed);
 
         Ok(())
     }
 
+    /// Resolving a deposit releases the held funds back to the client.
+    /// Resolving a withdrawal confirms it, so the held amount is dropped again.
     pub fn resolve(&mut self, id: TransactionId) -> EngineResult<()> {
-        let amount = self.find_dispute(id)?;
+        let (kind, asset, amount) = self.find_dispute(id)?;
 
         self.check_frozen()?;
-        self.check_held_founds(id, &amount)?;
+        self.check_held_founds(id, &asset, &amount)?;
 
-        self.available += amount;
-        self.held -= amount;
+        let target = match kind {
+            TradeKind::Deposit => LedgerAccount::Available,
+            TradeKind::Withdrawal => LedgerAccount::External,
+        };
 
-        self.disputes.remove(&id);
+        self.post(&[self.entry(
+            id,
+            EntryKind::Resolve,
+            &asset,
+            (LedgerAccount::Held, target),
+            amount,
+        )])?;
+
+        self.transition(id, TradeState::Resolved);
 
         Ok(())
     }
 
+    /// Charging back a deposit removes the held funds from the account.
+    /// Charging back a withdrawal credits the held amount back to the client.
+    /// In both cases the account is frozen.
     pub fn chargeback(&mut self, id: TransactionId) -> EngineResult<()> {
-        let amount = self.find_dispute(id)?;
+        let (kind, asset, amount) = self.find_dispute(id)?;
 
         self.check_frozen()?;
-        self.check_held_founds(id, &amount)?;
+        self.check_held_founds(id, &asset, &amount)?;
+
+        let target = match kind {
+            TradeKind::Deposit => LedgerAccount::External,
+            TradeKind::Withdrawal => LedgerAccount::Available,
+        };
 
-        self.total -= amount;
-        self.held -= amount;
-        self.locked = true;
+        self.post(&[self.entry(
+            id,
+            EntryKind::Chargeback,
+            &asset,
+            (LedgerAccount::Held, target),
+            amount,
+        )])?;
 
-        self.disputes.remove(&id);
+        self.lock = Some(LockReason::Chargeback(id));
+
+        self.transition(id, TradeState::ChargedBack);
 
         Ok(())
     }
 }
 
-fn validate_amount(id: TransactionId, amount: Decimal) -> EngineResult<Decimal> {
-    if amount.is_sign_negative() || amount.is_zero() {
-        Err(EngineError::NegativeAmount(id))
-    } else if amount.scale() > 4 {
-        Err(EngineError::InvalidPrecision(id))
-    } else {
-        Ok(amount)
-    }
+/// Amount and fee debited together, the sum of two valid amounts can still overflow.
+fn checked_total(id: TransactionId, amount: Decimal, fee: Decimal) -> EngineResult<Decimal> {
+    amount
+        .checked_add(fee)
+        .ok_or(EngineError::BalanceOverflow(id))
 }
 
 #[cfg(test)]
 mod tests {
     use crate::core::wallet::AccountWallet;
     use crate::errors::EngineError;
+    use crate::model::asset::Asset;
     use crate::model::client::ClientId;
     use crate::model::trade::TransactionId;
     use rust_decimal::Decimal;
@@ -173,7 +756,7 @@ mod tests {
         let trade_id = TransactionId(1);
         let amount = dec!(-100.0);
 
-        let result = account.deposit(trade_id, amount);
+        let result = account.deposit(trade_id, Asset::default(), amount);
 
         assert!(result.is_err());
 
@@ -189,7 +772,7 @@ mod tests {
         let trade_id = TransactionId(1);
         let amount = Decimal::ZERO;
 
-        let result = account.deposit(trade_id, amount);
+        let result = account.deposit(trade_id, Asset::default(), amount);
 
         assert!(result.is_err());
 
@@ -205,7 +788,7 @@ mod tests {
         let trade_id = TransactionId(1);
         let amount = dec!(1.12345);
 
-        let result = account.deposit(trade_id, amount);
+        let result = account.deposit(trade_id, Asset::default(), amount);
 
         assert!(result.is_err());
 
@@ -221,7 +804,7 @@ mod tests {
         let trade_id = TransactionId(1);
         let amount = dec!(1.1234);
 
-        let result = account.deposit(trade_id, amount);
+        let result = account.deposit(trade_id, Asset::default(), amount);
 
         assert!(result.is_ok());
     }
@@ -1,19 +1,51 @@
+use crate::core::command::{Operation, WalletTransaction};
+use crate::core::fees::{self, FeeCharge, FeeKind, FeePolicy};
+use crate::core::history::Spill;
+use crate::core::policy::WalletPolicy;
 use crate::core::wallet::AccountWallet;
-use crate::errors::EngineResult;
+use crate::errors::{EngineError, EngineResult};
+use crate::model::account::Account;
+use crate::model::asset::Asset;
 use crate::model::client::ClientId;
-use crate::model::trade::Transaction;
+use crate::model::ledger::JournalEntry;
+use crate::model::rejection::Origin;
+use crate::model::statement::{StatementLine, Step, StepKind};
+use crate::model::trade::{Timestamp, Transaction};
+use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::mem;
+use std::sync::Arc;
+use tracing::warn;
 
 pub struct EngineWorker {
     pub id: usize,
+    policy: WalletPolicy,
+    fees: Arc<dyn FeePolicy>,
+    spill: Option<Spill>,
     accounts: HashMap<ClientId, AccountWallet>,
+    posted: Vec<JournalEntry>,
 }
 
 impl EngineWorker {
-    pub fn new(id: usize) -> Self {
+    pub fn new(
+        id: usize,
+        policy: WalletPolicy,
+        fees: Arc<dyn FeePolicy>,
+        spill: Option<Spill>,
+        mut accounts: HashMap<ClientId, AccountWallet>,
+    ) -> Self {
+        for wallet in accounts.values_mut() {
+            wallet.set_policy(policy);
+            wallet.set_spill(spill.clone());
+        }
+
         Self {
             id,
-            accounts: HashMap::new(),
+            policy,
+            fees,
+            spill,
+            accounts,
+            posted: vec![],
         }
     }
 
@@ -21,29 +53,248 @@ impl EngineWorker {
         self.accounts
     }
 
-    pub fn handle(&mut self, trade: Transaction) -> EngineResult<()> {
-        let account = self.get_account(trade.client_id());
+    pub fn account(&self, client: ClientId, asset: &Asset) -> Option<Account> {
+        self.accounts
+            .get(&client)
+            .and_then(|wallet| wallet.account(asset))
+    }
+
+    pub fn balances(&self) -> Vec<Account> {
+        self.accounts
+            .values()
+            .flat_map(AccountWallet::accounts)
+            .collect()
+    }
+
+    pub fn snapshot(&self) -> Vec<AccountWallet> {
+        self.accounts.values().cloned().collect()
+    }
+
+    /// Applies the operation, returns the fee it charged if there was one.
+    /// The entries it posted are kept until `take_posted`.
+    pub fn handle(&mut self, operation: Operation) -> EngineResult<Option<FeeCharge>> {
+        let client = operation.client_id();
+        let result = self.execute(operation);
+
+        if let Some(wallet) = self.accounts.get_mut(&client) {
+            self.posted.extend(wallet.take_entries());
+        }
+
+        result
+    }
+
+    /// Journal entries posted since the last call, in order.
+    pub fn take_posted(&mut self) -> Vec<JournalEntry> {
+        mem::take(&mut self.posted)
+    }
+
+    fn execute(&mut self, operation: Operation) -> EngineResult<Option<FeeCharge>> {
+        let (client, trade) = (operation.client_id(), operation.trade_id());
+        let fees = self.fees.clone();
+        let precision = self.policy.precision;
+        let account = self.get_account(client);
+
+        let charged = match operation {
+            Operation::Apply(trade) => Self::apply(account, fees.as_ref(), precision, trade)?,
+            Operation::Reserve {
+                trade,
+                asset,
+                amount,
+                fee,
+                ..
+            } => account.reserve(trade, asset, amount, fee).map(|_| None)?,
+            Operation::Commit { trade, .. } => Some(account.commit(trade)?),
+            Operation::Release { trade, .. } => account.release(trade).map(|_| None)?,
+            Operation::Credit {
+                trade,
+                asset,
+                amount,
+                ..
+            } => account.credit(trade, asset, amount).map(|_| None)?,
+            Operation::Collect {
+                trade,
+                asset,
+                amount,
+                ..
+            } => account.collect(trade, asset, amount).map(|_| None)?,
+            Operation::Lock { reason, .. } => account.lock(reason).map(|_| None)?,
+            Operation::Unlock { .. } => account.unlock().map(|_| None)?,
+        };
+
+        Ok(charged
+            .filter(|(_, fee)| !fee.is_zero())
+            .zip(trade)
+            .map(|((asset, amount), trade)| FeeCharge {
+                client,
+                trade,
+                asset,
+                amount,
+            }))
+    }
+
+    /// The clock of the wallet moves to the timestamp of the transaction only once it is accepted.
+    fn apply(
+        account: &mut AccountWallet,
+        fees: &dyn FeePolicy,
+        precision: u32,
+        trade: WalletTransaction,
+    ) -> EngineResult<Option<(Asset, Decimal)>> {
+        let timestamp = trade.timestamp();
 
+        let charged = Self::apply_at(account, fees, precision, trade, timestamp)?;
+
+        if let Some(timestamp) = timestamp {
+            account.advance(timestamp);
+        }
+
+        Ok(charged)
+    }
+
+    fn apply_at(
+        account: &mut AccountWallet,
+        fees: &dyn FeePolicy,
+        precision: u32,
+        trade: WalletTransaction,
+        timestamp: Option<Timestamp>,
+    ) -> EngineResult<Option<(Asset, Decimal)>> {
         match trade {
-            Transaction::Deposit {
+            WalletTransaction::Deposit {
                 client: _,
                 trade,
                 amount,
-            } => account.deposit(trade, amount),
-            Transaction::Withdrawal {
+                asset,
+                ..
+            } => {
+                let fee = fees::compute(fees, FeeKind::Deposit, amount, precision);
+                account.deposit_with_fee(trade, asset.clone(), amount, fee, timestamp)?;
+                Ok(Some((asset, fee)))
+            }
+            WalletTransaction::Withdrawal {
                 client: _,
                 trade,
                 amount,
-            } => account.withdrawal(trade, amount),
-            Transaction::Dispute { client: _, trade } => account.dispute(trade),
-            Transaction::Resolve { client: _, trade } => account.resolve(trade),
-            Transaction::Chargeback { client: _, trade } => account.chargeback(trade),
+                asset,
+                ..
+            } => {
+                let fee = fees::compute(fees, FeeKind::Withdrawal, amount, precision);
+                account.withdrawal_with_fee(trade, asset.clone(), amount, fee, timestamp)?;
+                Ok(Some((asset, fee)))
+            }
+            WalletTransaction::Dispute { trade, .. } => {
+                account.dispute_at(trade, timestamp).map(|_| None)
+            }
+            WalletTransaction::Resolve { client: _, trade } => account.resolve(trade).map(|_| None),
+            WalletTransaction::Chargeback { client: _, trade } => {
+                account.chargeback(trade)?;
+
+                let Some((asset, amount)) = account.trade(trade) else {
+                    return Ok(None);
+                };
+
+                let fee = fees::compute(fees, FeeKind::Chargeback, amount, precision);
+
+                // The chargeback is already applied, a fee which cannot be posted is waived.
+                if let Err(error) = account.charge(trade, &asset, fee) {
+                    warn!("Chargeback fee has not been charged: {}", error);
+                    return Ok(None);
+                }
+
+                Ok(Some((asset, fee)))
+            }
         }
     }
 
+    /// Statement step of an operation. Reserving and releasing funds of a transfer are not steps
+    /// of their own, the transfer is shown once committed or rejected.
+    pub fn step(&self, operation: &Operation) -> Option<Step> {
+        let client = operation.client_id();
+
+        match operation {
+            Operation::Apply(tx) => {
+                Some(self.locate(client, Step::from(&Transaction::from(tx.clone()))))
+            }
+            Operation::Commit { trade, .. } => {
+                let (asset, amount) = self.accounts.get(&client)?.reservation(*trade)?;
+
+                Some(Step {
+                    kind: StepKind::Transfer,
+                    tx: Some(*trade),
+                    asset,
+                    amount: Some(amount),
+                })
+            }
+            Operation::Credit {
+                trade,
+                asset,
+                amount,
+                ..
+            }
+            | Operation::Collect {
+                trade,
+                asset,
+                amount,
+                ..
+            } => Some(Step {
+                kind: StepKind::Credit,
+                tx: Some(*trade),
+                asset: asset.clone(),
+                amount: Some(*amount),
+            }),
+            Operation::Lock { .. } => Some(Step {
+                kind: StepKind::Lock,
+                tx: None,
+                asset: Asset::default(),
+                amount: None,
+            }),
+            Operation::Unlock { .. } => Some(Step {
+                kind: StepKind::Unlock,
+                tx: None,
+                asset: Asset::default(),
+                amount: None,
+            }),
+            Operation::Reserve { .. } | Operation::Release { .. } => None,
+        }
+    }
+
+    /// Disputes, resolves and chargebacks take the asset and amount of the disputed transaction.
+    pub fn locate(&self, client: ClientId, mut step: Step) -> Step {
+        let disputed = matches!(
+            step.kind,
+            StepKind::Dispute | StepKind::Resolve | StepKind::Chargeback
+        );
+
+        let trade = step
+            .tx
+            .filter(|_| disputed)
+            .and_then(|id| self.accounts.get(&client)?.trade(id));
+
+        if let Some((asset, amount)) = trade {
+            step.asset = asset;
+            step.amount = Some(amount);
+        }
+
+        step
+    }
+
+    pub fn statement_line(
+        &self,
+        client: ClientId,
+        step: Step,
+        origin: Option<&Origin>,
+        error: Option<&EngineError>,
+    ) -> StatementLine {
+        let account = self.account(client, &step.asset);
+
+        StatementLine::new(step, origin, error, account.as_ref())
+    }
+
     fn get_account(&mut self, client_id: ClientId) -> &mut AccountWallet {
-        self.accounts
-            .entry(client_id)
-            .or_insert_with(|| AccountWallet::new(client_id))
+        let (policy, spill) = (self.policy, &self.spill);
+
+        self.accounts.entry(client_id).or_insert_with(|| {
+            let mut wallet = AccountWallet::with_policy(client_id, policy);
+            wallet.set_spill(spill.clone());
+            wallet
+        })
     }
 }
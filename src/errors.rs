@@ -1,41 +1,262 @@
 use crate::model::client::ClientId;
 use crate::model::trade::TransactionId;
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::io::ErrorKind;
+use std::sync::Arc;
 use thiserror::Error;
 
 pub type EngineResult<T> = Result<T, EngineError>;
 
-#[derive(Error, Debug, PartialEq, Eq)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum EngineError {
     #[error("Cannot find transaction: {0}")]
     TransactionNotFound(TransactionId),
     #[error("Client account is frozen: {0}")]
     FrozenAccount(ClientId),
+    #[error("Client account is not frozen: {0}")]
+    NotLocked(ClientId),
     #[error("Precision is invalid for transaction: {0}")]
     InvalidPrecision(TransactionId),
     #[error("Negative amount detected for transaction: {0}")]
     NegativeAmount(TransactionId),
     #[error("Not enough funds to process transaction: {0}")]
     NotEnoughMany(TransactionId),
+    #[error("Transaction already processed: {0}")]
+    DuplicateTransaction(TransactionId),
+    #[error("Transaction is already disputed: {0}")]
+    AlreadyDisputed(TransactionId),
+    #[error("Transaction dispute is already resolved: {0}")]
+    AlreadyResolved(TransactionId),
+    #[error("Transaction is already charged back: {0}")]
+    AlreadyChargedBack(TransactionId),
+    #[error("Transaction is not disputed: {0}")]
+    NotDisputed(TransactionId),
     #[error("Deposit or withdraw need to has amount")]
     MissingAmount(),
-    #[error("Csv error: {0}")]
-    Csv(String),
+    #[error("Transfer need to has recipient")]
+    MissingRecipient(),
+    #[error("Transfer sender and recipient has to be different: {0}")]
+    InvalidTransfer(TransactionId),
+    #[error("Transaction is too old to be disputed: {0}")]
+    DisputeWindowExpired(TransactionId),
+    #[error("Balance would overflow with transaction: {0}")]
+    BalanceOverflow(TransactionId),
+    #[error("Timestamp is too far in the future for transaction: {0}")]
+    FutureTimestamp(TransactionId),
+    #[error("Csv error{}: {source}", at_line(.position.as_ref().map(|position| position.line())))]
+    Csv {
+        position: Option<csv::Position>,
+        #[source]
+        source: ErrorSource,
+    },
+    #[error("Csv io error{}: {source}", at_line(.position.as_ref().map(|position| position.line())))]
+    CsvIo {
+        position: Option<csv::Position>,
+        #[source]
+        source: ErrorSource,
+    },
+    #[error("Json error{}: {source}", at_line(*.line))]
+    Json {
+        line: Option<u64>,
+        #[source]
+        source: ErrorSource,
+    },
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Snapshot error: {0}")]
+    Snapshot(#[source] ErrorSource),
+    #[error("Write-ahead log error: {0}")]
+    Wal(#[source] ErrorSource),
     #[error("File not found: {0}")]
-    FileNotFound(String),
+    FileNotFound(#[source] ErrorSource),
+    #[error("Io error: {0}")]
+    Io(#[source] ErrorSource),
+    #[error("Serialization error: {0}")]
+    Serialization(#[source] ErrorSource),
     #[error("Input file not provided")]
     InputNotProvided(),
+    #[error("Worker {0} has failed, clients with id % {1} = {0} are unavailable")]
+    WorkerFailed(usize, u16),
     #[error("Unknown payment core error")]
     InternalError(),
 }
 
+impl EngineError {
+    /// Stable code for external reporting: 1xxx business rules, 2xxx invalid input,
+    /// 3xxx files and storage, 9xxx internal failures. Codes are never reused.
+    pub fn code(&self) -> u16 {
+        match self {
+            EngineError::TransactionNotFound(_) => 1001,
+            EngineError::FrozenAccount(_) => 1002,
+            EngineError::NotLocked(_) => 1003,
+            EngineError::NotEnoughMany(_) => 1004,
+            EngineError::DuplicateTransaction(_) => 1005,
+            EngineError::AlreadyDisputed(_) => 1006,
+            EngineError::AlreadyResolved(_) => 1007,
+            EngineError::AlreadyChargedBack(_) => 1008,
+            EngineError::NotDisputed(_) => 1009,
+            EngineError::InvalidTransfer(_) => 1010,
+            EngineError::DisputeWindowExpired(_) => 1011,
+            EngineError::BalanceOverflow(_) => 1012,
+            EngineError::InvalidPrecision(_) => 2001,
+            EngineError::NegativeAmount(_) => 2002,
+            EngineError::MissingAmount() => 2003,
+            EngineError::MissingRecipient() => 2004,
+            EngineError::Csv { .. } => 2005,
+            EngineError::Json { .. } => 2006,
+            EngineError::FutureTimestamp(_) => 2007,
+            EngineError::FileNotFound(_) => 3001,
+            EngineError::InputNotProvided() => 3002,
+            EngineError::Snapshot(_) => 3003,
+            EngineError::Wal(_) => 3004,
+            EngineError::InvalidConfig(_) => 3005,
+            EngineError::Io(_) => 3006,
+            EngineError::Serialization(_) => 3007,
+            EngineError::CsvIo { .. } => 3008,
+            EngineError::InternalError() => 9001,
+            EngineError::WorkerFailed(..) => 9002,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EngineError::TransactionNotFound(_) => "TransactionNotFound",
+            EngineError::FrozenAccount(_) => "FrozenAccount",
+            EngineError::NotLocked(_) => "NotLocked",
+            EngineError::NotEnoughMany(_) => "NotEnoughMany",
+            EngineError::DuplicateTransaction(_) => "DuplicateTransaction",
+            EngineError::AlreadyDisputed(_) => "AlreadyDisputed",
+            EngineError::AlreadyResolved(_) => "AlreadyResolved",
+            EngineError::AlreadyChargedBack(_) => "AlreadyChargedBack",
+            EngineError::NotDisputed(_) => "NotDisputed",
+            EngineError::InvalidTransfer(_) => "InvalidTransfer",
+            EngineError::DisputeWindowExpired(_) => "DisputeWindowExpired",
+            EngineError::BalanceOverflow(_) => "BalanceOverflow",
+            EngineError::InvalidPrecision(_) => "InvalidPrecision",
+            EngineError::NegativeAmount(_) => "NegativeAmount",
+            EngineError::MissingAmount() => "MissingAmount",
+            EngineError::MissingRecipient() => "MissingRecipient",
+            EngineError::Csv { .. } => "Csv",
+            EngineError::Json { .. } => "Json",
+            EngineError::FutureTimestamp(_) => "FutureTimestamp",
+            EngineError::FileNotFound(_) => "FileNotFound",
+            EngineError::InputNotProvided() => "InputNotProvided",
+            EngineError::Snapshot(_) => "Snapshot",
+            EngineError::Wal(_) => "Wal",
+            EngineError::InvalidConfig(_) => "InvalidConfig",
+            EngineError::Io(_) => "Io",
+            EngineError::Serialization(_) => "Serialization",
+            EngineError::CsvIo { .. } => "CsvIo",
+            EngineError::InternalError() => "InternalError",
+            EngineError::WorkerFailed(..) => "WorkerFailed",
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self.code() / 1000 {
+            1 => ErrorCategory::Business,
+            2 => ErrorCategory::Input,
+            3 => ErrorCategory::Storage,
+            _ => ErrorCategory::Internal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ErrorCategory {
+    Business,
+    Input,
+    Storage,
+    Internal,
+}
+
+/// Underlying io, csv or json error, kept for `Error::source` chains.
+///
+/// Shared, so rejections can be cloned, and compared by message, so `EngineError` stays `Eq`.
+#[derive(Clone)]
+pub struct ErrorSource(Arc<dyn StdError + Send + Sync>);
+
+impl ErrorSource {
+    pub fn new<E: StdError + Send + Sync + 'static>(error: E) -> ErrorSource {
+        ErrorSource(Arc::new(error))
+    }
+
+    pub fn get(&self) -> &(dyn StdError + Send + Sync + 'static) {
+        self.0.as_ref()
+    }
+}
+
+impl Debug for ErrorSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for ErrorSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl StdError for ErrorSource {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(self.0.as_ref())
+    }
+}
+
+impl PartialEq for ErrorSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for ErrorSource {}
+
+fn at_line(line: Option<u64>) -> String {
+    line.map(|line| format!(" at line {}", line))
+        .unwrap_or_default()
+}
+
 impl From<std::io::Error> for EngineError {
     fn from(error: std::io::Error) -> Self {
-        EngineError::FileNotFound(error.to_string())
+        match error.kind() {
+            ErrorKind::NotFound => EngineError::FileNotFound(ErrorSource::new(error)),
+            _ => EngineError::Io(ErrorSource::new(error)),
+        }
     }
 }
 
 impl From<csv::Error> for EngineError {
     fn from(error: csv::Error) -> Self {
-        EngineError::Csv(error.to_string())
+        let position = error.position().cloned();
+
+        EngineError::from_csv(error, position)
+    }
+}
+
+impl EngineError {
+    /// Io errors of csv have no position of their own, the reader passes the one it stopped at.
+    pub(crate) fn from_csv(error: csv::Error, position: Option<csv::Position>) -> EngineError {
+        if error.is_io_error() {
+            return EngineError::CsvIo {
+                position,
+                source: ErrorSource::new(error),
+            };
+        }
+
+        EngineError::Csv {
+            position,
+            source: ErrorSource::new(error),
+        }
+    }
+}
+
+impl From<serde_json::Error> for EngineError {
+    fn from(error: serde_json::Error) -> Self {
+        match error.io_error_kind() {
+            Some(_) => EngineError::Io(ErrorSource::new(std::io::Error::from(error))),
+            None => EngineError::Serialization(ErrorSource::new(error)),
+        }
     }
 }
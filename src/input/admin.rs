@@ -0,0 +1,37 @@
+use crate::errors::EngineResult;
+use crate::model::admin::AdminCommand;
+use csv::ReaderBuilder;
+use std::fs::File;
+
+/// Reads operator commands (`action,client,operator,reason`) from a file kept apart
+/// from the client transactions stream.
+pub struct AdminReader {
+    iterator: csv::DeserializeRecordsIntoIter<File, AdminCommand>,
+}
+
+impl AdminReader {
+    pub fn new(path: &str) -> EngineResult<AdminReader> {
+        Self::from_file(File::open(path)?)
+    }
+
+    pub fn from_file(file: File) -> EngineResult<AdminReader> {
+        let reader = ReaderBuilder::new()
+            .has_headers(true)
+            .trim(csv::Trim::All)
+            .from_reader(file);
+
+        Ok(AdminReader {
+            iterator: reader.into_deserialize::<AdminCommand>(),
+        })
+    }
+}
+
+impl Iterator for AdminReader {
+    type Item = EngineResult<AdminCommand>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iterator
+            .next()
+            .map(|result| result.map_err(|error| error.into()))
+    }
+}
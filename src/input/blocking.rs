@@ -0,0 +1,70 @@
+use crate::errors::{EngineError, EngineResult};
+use crate::input::reader::InputReader;
+use crate::model::rejection::Origin;
+use crate::model::trade::Transaction;
+use std::pin::Pin;
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Transaction read from a source together with its position.
+#[derive(Debug)]
+pub struct ReadRecord {
+    pub transaction: EngineResult<Transaction>,
+    pub origin: Option<Origin>,
+}
+
+/// Async source of transactions, can be composed with `tokio_stream::StreamExt` combinators.
+pub type TransactionStream = Pin<Box<dyn Stream<Item = ReadRecord> + Send>>;
+
+/// Bridges a blocking reader to a stream. The reader still does blocking io, but on a dedicated
+/// thread, so slow sources never stall the runtime. At most `buffer` records are read ahead
+/// of the consumer.
+pub fn from_reader<R>(reader: R, buffer: usize) -> TransactionStream
+where
+    R: InputReader + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(buffer);
+
+    tokio::task::spawn_blocking(move || forward(reader, sender));
+
+    Box::pin(ReceiverStream::new(receiver))
+}
+
+/// Same as `from_reader`, but the reader is also created on the blocking thread, as opening
+/// a file and reading its header are blocking too. Fails if the reader cannot be created.
+pub async fn open<F, R>(open: F, buffer: usize) -> EngineResult<TransactionStream>
+where
+    F: FnOnce() -> EngineResult<R> + Send + 'static,
+    R: InputReader + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel(buffer);
+    let (opened, result) = oneshot::channel();
+
+    tokio::task::spawn_blocking(move || match open() {
+        Ok(reader) => {
+            let _ = opened.send(Ok(()));
+            forward(reader, sender);
+        }
+        Err(error) => {
+            let _ = opened.send(Err(error));
+        }
+    });
+
+    result.await.map_err(|_| EngineError::InternalError())??;
+
+    Ok(Box::pin(ReceiverStream::new(receiver)))
+}
+
+fn forward<R: InputReader>(mut reader: R, sender: mpsc::Sender<ReadRecord>) {
+    while let Some(transaction) = reader.next() {
+        let record = ReadRecord {
+            transaction,
+            origin: reader.origin(),
+        };
+
+        if sender.blocking_send(record).is_err() {
+            break;
+        }
+    }
+}
@@ -1,12 +1,17 @@
-use crate::errors::EngineResult;
+use crate::errors::{EngineError, EngineResult};
 use crate::input::reader::InputReader;
 use crate::input::row::TransactionRow;
+use crate::model::rejection::Origin;
 use crate::model::trade::Transaction;
-use csv::ReaderBuilder;
+use csv::{ReaderBuilder, StringRecord, Terminator, WriterBuilder};
 use std::fs::File;
+use std::io::Read;
 
-pub struct CsvReader {
-    iterator: csv::DeserializeRecordsIntoIter<File, TransactionRow>,
+pub struct CsvReader<R = File> {
+    reader: csv::Reader<R>,
+    headers: StringRecord,
+    record: StringRecord,
+    origin: Option<Origin>,
 }
 
 impl CsvReader {
@@ -15,20 +20,62 @@ impl CsvReader {
     }
 
     pub fn from_file(file: File) -> EngineResult<CsvReader> {
-        let reader = ReaderBuilder::new()
-            .has_headers(true)
-            .trim(csv::Trim::All)
-            .from_reader(file);
+        Self::from_reader(file)
+    }
+}
+
+impl<R: Read> CsvReader<R> {
+    /// Reads from any source, e.g. stdin, a pipe, a socket or an in-memory buffer.
+    pub fn from_reader(source: R) -> EngineResult<CsvReader<R>> {
+        let mut reader = ReaderBuilder::new().has_headers(true).from_reader(source);
+
+        let mut headers = reader.headers()?.clone();
+        headers.trim();
 
         Ok(CsvReader {
-            iterator: reader.into_deserialize::<TransactionRow>(),
+            reader,
+            headers,
+            record: StringRecord::new(),
+            origin: None,
         })
     }
 }
 
-impl InputReader for CsvReader {
+impl<R: Read> InputReader for CsvReader<R> {
     fn next(&mut self) -> Option<EngineResult<Transaction>> {
-        self.iterator.next().map(to_dto_model)
+        match self.reader.read_record(&mut self.record) {
+            Ok(false) => None,
+            Ok(true) => {
+                self.origin = self.record.position().map(|position| Origin {
+                    line: position.line(),
+                    row: raw_row(&self.record),
+                });
+
+                self.record.trim();
+
+                Some(to_dto_model(
+                    self.record
+                        .deserialize::<TransactionRow>(Some(&self.headers)),
+                ))
+            }
+            Err(error) => {
+                let position = error
+                    .position()
+                    .cloned()
+                    .or_else(|| Some(self.reader.position().clone()));
+
+                self.origin = position.as_ref().map(|position| Origin {
+                    line: position.line(),
+                    row: raw_row(&self.record),
+                });
+
+                Some(Err(EngineError::from_csv(error, position)))
+            }
+        }
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        self.origin.clone()
     }
 }
 
@@ -38,3 +85,20 @@ fn to_dto_model(result: Result<TransactionRow, csv::Error>) -> EngineResult<Tran
         Err(error) => Err(error.into()),
     }
 }
+
+/// Written back as csv, so a field with a comma or a quote is quoted like in the input.
+fn raw_row(record: &StringRecord) -> String {
+    let mut writer = WriterBuilder::new()
+        .terminator(Terminator::Any(b'\n'))
+        .from_writer(vec![]);
+
+    let row = writer
+        .write_record(record)
+        .ok()
+        .and_then(|_| writer.into_inner().ok())
+        .unwrap_or_default();
+
+    String::from_utf8_lossy(&row)
+        .trim_end_matches('\n')
+        .to_string()
+}
@@ -0,0 +1,84 @@
+use crate::errors::{EngineError, EngineResult, ErrorSource};
+use crate::input::reader::InputReader;
+use crate::input::row::TransactionRow;
+use crate::model::rejection::Origin;
+use crate::model::trade::Transaction;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines, Read};
+
+/// Reads one transaction object per line (NDJSON), with the same fields as the csv file.
+/// Amounts can be json numbers or strings, both are parsed without precision loss.
+pub struct JsonLinesReader<R = File> {
+    lines: Lines<BufReader<R>>,
+    line: u64,
+    origin: Option<Origin>,
+}
+
+impl JsonLinesReader {
+    pub fn new(path: &str) -> EngineResult<JsonLinesReader> {
+        Self::from_file(File::open(path)?)
+    }
+
+    pub fn from_file(file: File) -> EngineResult<JsonLinesReader> {
+        Self::from_reader(file)
+    }
+}
+
+impl<R: Read> JsonLinesReader<R> {
+    pub fn from_reader(source: R) -> EngineResult<JsonLinesReader<R>> {
+        Ok(JsonLinesReader {
+            lines: BufReader::new(source).lines(),
+            line: 0,
+            origin: None,
+        })
+    }
+}
+
+impl<R: Read> InputReader for JsonLinesReader<R> {
+    fn next(&mut self) -> Option<EngineResult<Transaction>> {
+        loop {
+            let line = self.lines.next()?;
+            self.line += 1;
+
+            let row = match line {
+                Ok(row) if row.trim().is_empty() => continue,
+                Ok(row) => row,
+                Err(error) => {
+                    self.origin = Some(Origin {
+                        line: self.line,
+                        row: String::new(),
+                    });
+
+                    return Some(Err(error.into()));
+                }
+            };
+
+            let transaction = parse_line(&row, Some(self.line));
+
+            self.origin = Some(Origin {
+                line: self.line,
+                row,
+            });
+
+            return Some(transaction);
+        }
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        self.origin.clone()
+    }
+}
+
+/// Parses a single transaction object, validated like a csv row.
+pub fn parse(text: &str) -> EngineResult<Transaction> {
+    parse_line(text, None)
+}
+
+fn parse_line(text: &str, line: Option<u64>) -> EngineResult<Transaction> {
+    serde_json::from_str::<TransactionRow>(text)
+        .map_err(|error| EngineError::Json {
+            line,
+            source: ErrorSource::new(error),
+        })
+        .and_then(Transaction::try_from)
+}
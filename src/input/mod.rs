@@ -1,3 +1,6 @@
+pub mod admin;
+pub mod blocking;
 pub mod csv;
+pub mod json;
 pub mod reader;
 mod row;
@@ -1,6 +1,22 @@
 use crate::errors::EngineResult;
+use crate::model::rejection::Origin;
 use crate::model::trade::Transaction;
 
 pub trait InputReader {
     fn next(&mut self) -> Option<EngineResult<Transaction>>;
+
+    /// Position of the record returned by the last `next` call, if the source tracks it.
+    fn origin(&self) -> Option<Origin> {
+        None
+    }
+}
+
+impl<R: InputReader + ?Sized> InputReader for Box<R> {
+    fn next(&mut self) -> Option<EngineResult<Transaction>> {
+        (**self).next()
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        (**self).origin()
+    }
 }
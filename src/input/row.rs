@@ -1,6 +1,7 @@
 use crate::errors::{EngineError, EngineResult};
+use crate::model::asset::Asset;
 use crate::model::client::ClientId;
-use crate::model::trade::{Transaction, TransactionId};
+use crate::model::trade::{Timestamp, Transaction, TransactionId};
 use rust_decimal::Decimal;
 use serde::Deserialize;
 
@@ -9,6 +10,7 @@ use serde::Deserialize;
 enum TransactionType {
     Deposit,
     Withdrawal,
+    Transfer,
     Dispute,
     Resolve,
     Chargeback,
@@ -20,12 +22,26 @@ pub struct TransactionRow {
     client: ClientId,
     tx: TransactionId,
     amount: Option<Decimal>,
+    #[serde(default)]
+    to: Option<ClientId>,
+    #[serde(default)]
+    currency: Option<Asset>,
+    #[serde(default)]
+    timestamp: Option<Timestamp>,
 }
 
 impl TransactionRow {
     fn get_amount(&self) -> EngineResult<Decimal> {
         self.amount.ok_or(EngineError::MissingAmount())
     }
+
+    fn get_recipient(&self) -> EngineResult<ClientId> {
+        self.to.ok_or(EngineError::MissingRecipient())
+    }
+
+    fn get_asset(&self) -> Asset {
+        self.currency.clone().unwrap_or_default()
+    }
 }
 
 impl TryFrom<TransactionRow> for Transaction {
@@ -37,15 +53,27 @@ impl TryFrom<TransactionRow> for Transaction {
                 client: row.client,
                 trade: row.tx,
                 amount: row.get_amount()?,
+                asset: row.get_asset(),
+                timestamp: row.timestamp,
             }),
             TransactionType::Withdrawal => Ok(Transaction::Withdrawal {
                 client: row.client,
                 trade: row.tx,
                 amount: row.get_amount()?,
+                asset: row.get_asset(),
+                timestamp: row.timestamp,
+            }),
+            TransactionType::Transfer => Ok(Transaction::Transfer {
+                client: row.client,
+                to: row.get_recipient()?,
+                trade: row.tx,
+                amount: row.get_amount()?,
+                asset: row.get_asset(),
             }),
             TransactionType::Dispute => Ok(Transaction::Dispute {
                 client: row.client,
                 trade: row.tx,
+                timestamp: row.timestamp,
             }),
             TransactionType::Resolve => Ok(Transaction::Resolve {
                 client: row.client,
@@ -63,6 +91,7 @@ impl TryFrom<TransactionRow> for Transaction {
 mod tests {
     use crate::errors::{EngineError, EngineResult};
     use crate::input::row::{TransactionRow, TransactionType};
+    use crate::model::asset::Asset;
     use crate::model::client::ClientId;
     use crate::model::trade::{Transaction, TransactionId};
     use rust_decimal_macros::dec;
@@ -74,6 +103,9 @@ mod tests {
             client: ClientId(1),
             tx: TransactionId(2),
             amount: Some(dec!(1.5)),
+            to: None,
+            currency: None,
+            timestamp: None,
         };
 
         let dto: EngineResult<Transaction> = row.try_into();
@@ -85,6 +117,8 @@ mod tests {
                 client: ClientId(1),
                 trade: TransactionId(2),
                 amount: dec!(1.5),
+                asset: Asset::default(),
+                timestamp: None,
             }
         );
 
@@ -98,6 +132,9 @@ mod tests {
             client: ClientId(1),
             tx: TransactionId(2),
             amount: None,
+            to: None,
+            currency: None,
+            timestamp: None,
         };
 
         let dto: EngineResult<Transaction> = row.try_into();
@@ -115,6 +152,9 @@ mod tests {
             client: ClientId(1),
             tx: TransactionId(2),
             amount: Some(dec!(1.5)),
+            to: None,
+            currency: None,
+            timestamp: None,
         };
 
         let dto: EngineResult<Transaction> = row.try_into();
@@ -126,6 +166,8 @@ mod tests {
                 client: ClientId(1),
                 trade: TransactionId(2),
                 amount: dec!(1.5),
+                asset: Asset::default(),
+                timestamp: None,
             }
         );
 
@@ -139,6 +181,9 @@ mod tests {
             client: ClientId(1),
             tx: TransactionId(2),
             amount: None,
+            to: None,
+            currency: None,
+            timestamp: None,
         };
 
         let dto: EngineResult<Transaction> = row.try_into();
@@ -149,6 +194,55 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_valid_from_for_transfer() -> EngineResult<()> {
+        let row = TransactionRow {
+            r#type: TransactionType::Transfer,
+            client: ClientId(1),
+            tx: TransactionId(2),
+            amount: Some(dec!(1.5)),
+            to: Some(ClientId(3)),
+            currency: None,
+            timestamp: None,
+        };
+
+        let dto: EngineResult<Transaction> = row.try_into();
+
+        assert!(dto.is_ok());
+        assert_eq!(
+            dto?,
+            Transaction::Transfer {
+                client: ClientId(1),
+                to: ClientId(3),
+                trade: TransactionId(2),
+                amount: dec!(1.5),
+                asset: Asset::default(),
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_from_for_transfer() -> EngineResult<()> {
+        let row = TransactionRow {
+            r#type: TransactionType::Transfer,
+            client: ClientId(1),
+            tx: TransactionId(2),
+            amount: Some(dec!(1.5)),
+            to: None,
+            currency: None,
+            timestamp: None,
+        };
+
+        let dto: EngineResult<Transaction> = row.try_into();
+
+        assert!(dto.is_err());
+        assert_eq!(dto, Err(EngineError::MissingRecipient()));
+
+        Ok(())
+    }
+
     #[test]
     fn test_valid_from_for_dispute() -> EngineResult<()> {
         let row = TransactionRow {
@@ -156,6 +250,9 @@ mod tests {
             client: ClientId(1),
             tx: TransactionId(2),
             amount: None,
+            to: None,
+            currency: None,
+            timestamp: None,
         };
 
         let dto: EngineResult<Transaction> = row.try_into();
@@ -166,6 +263,7 @@ mod tests {
             Transaction::Dispute {
                 client: ClientId(1),
                 trade: TransactionId(2),
+                timestamp: None,
             }
         );
 
@@ -179,6 +277,9 @@ mod tests {
             client: ClientId(1),
             tx: TransactionId(2),
             amount: None,
+            to: None,
+            currency: None,
+            timestamp: None,
         };
 
         let dto: EngineResult<Transaction> = row.try_into();
@@ -202,6 +303,9 @@ mod tests {
             client: ClientId(1),
             tx: TransactionId(2),
             amount: None,
+            to: None,
+            currency: None,
+            timestamp: None,
         };
 
         let dto: EngineResult<Transaction> = row.try_into();
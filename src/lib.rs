@@ -2,3 +2,5 @@ pub mod core;
 pub mod errors;
 pub mod input;
 pub mod model;
+pub mod output;
+pub mod server;
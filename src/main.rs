@@ -1,41 +1,485 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use payment_engine::core::builder::{EngineConfig, PaymentEngineBuilder};
 use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::policy::DuplicatePolicy;
+use payment_engine::core::snapshot::EngineSnapshot;
+use payment_engine::core::supervisor::Supervision;
 use payment_engine::errors::{EngineError, EngineResult};
+use payment_engine::input::admin::AdminReader;
+use payment_engine::input::blocking::{self, ReadRecord};
 use payment_engine::input::csv::CsvReader;
+use payment_engine::input::json::JsonLinesReader;
 use payment_engine::input::reader::InputReader;
-use std::env;
+use payment_engine::model::admin::AuditEntry;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::ledger::JournalEntry;
+use payment_engine::model::rejection::Rejection;
+use payment_engine::model::report::ReportOrder;
+use payment_engine::output::audit::AuditWriter;
+use payment_engine::output::journal::JournalWriter;
+use payment_engine::output::rejects::RejectsWriter;
+use payment_engine::output::report::ReportFormat;
+use payment_engine::server::http::{self, ApiState};
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
 use tracing::{info, warn};
 
+const READ_AHEAD_SIZE: usize = 1000;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum InputFormat {
+    Csv,
+    Jsonl,
+}
+
+impl InputFormat {
+    fn detect(file: &str) -> InputFormat {
+        match Path::new(file).extension().and_then(|e| e.to_str()) {
+            Some("json" | "jsonl" | "ndjson") => InputFormat::Jsonl,
+            _ => InputFormat::Csv,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum OutputFormat {
+    #[default]
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl From<OutputFormat> for ReportFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Csv => ReportFormat::Csv,
+            OutputFormat::Json => ReportFormat::Json,
+            OutputFormat::Ndjson => ReportFormat::Ndjson,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum SortKey {
+    #[default]
+    Client,
+    Available,
+    Held,
+    Total,
+}
+
+impl From<SortKey> for ReportOrder {
+    fn from(key: SortKey) -> Self {
+        match key {
+            SortKey::Client => ReportOrder::Client,
+            SortKey::Available => ReportOrder::Available,
+            SortKey::Held => ReportOrder::Held,
+            SortKey::Total => ReportOrder::Total,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Mode {
+    /// Serves the engine over http until ctrl-c, rejections are available from the api
+    Serve {
+        /// Address the server listens on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Prints every transaction applied to or rejected for a client, with its balances after it
+    Statement {
+        /// Client the statement is printed for
+        client: u16,
+        /// Csv or json lines file with client transactions, `-` reads them from stdin
+        file: String,
+    },
+}
+
+#[derive(Parser)]
+#[command(about = "Simple payment engine")]
+struct Args {
+    #[command(subcommand)]
+    mode: Option<Mode>,
+    /// Csv or json lines file with client transactions, `-` reads them from stdin
+    file: Option<String>,
+    /// Input format, detected from the file extension when omitted
+    #[arg(long, value_enum)]
+    format: Option<InputFormat>,
+    /// Report format printed to stdout
+    #[arg(long, value_enum, default_value_t)]
+    output: OutputFormat,
+    /// Report rows order, ties are ordered by client
+    #[arg(long, value_enum, default_value_t)]
+    sort: SortKey,
+    /// Engine state saved by a previous run to continue from
+    #[arg(long, global = true)]
+    snapshot: Option<String>,
+    /// File where the engine state is saved after processing
+    #[arg(long, global = true)]
+    checkpoint: Option<String>,
+    /// Write-ahead log replayed on start and appended with every accepted transaction
+    #[arg(long, global = true)]
+    wal: Option<String>,
+    /// Csv file with operator lock/unlock commands, applied after the transactions
+    #[arg(long, global = true)]
+    admin: Option<String>,
+    /// Csv (or json lines for .json/.jsonl/.ndjson) file where rejected transactions are written
+    #[arg(long)]
+    rejects: Option<String>,
+    /// Toml file with engine options, flags take precedence over it
+    #[arg(long, global = true)]
+    config: Option<String>,
+    /// Number of workers, clients are spread over them by `client_id % workers`
+    #[arg(long, global = true)]
+    workers: Option<usize>,
+    /// Capacity of the channel of every worker
+    #[arg(long, global = true)]
+    buffer: Option<usize>,
+    /// Maximal number of decimal places of an amount
+    #[arg(long, global = true)]
+    precision: Option<u32>,
+    /// Allow disputing a transaction again once its dispute is resolved
+    #[arg(long, global = true)]
+    allow_redispute: bool,
+    /// Drop transactions with an already seen tx id instead of rejecting them
+    #[arg(long, global = true)]
+    ignore_duplicates: bool,
+    /// Restart a failed worker by replaying its shard instead of rejecting its clients
+    #[arg(long, global = true)]
+    restart_workers: bool,
+    /// Client credited with the fees configured in the config file
+    #[arg(long, global = true)]
+    house_account: Option<u16>,
+    /// Csv file the audit trail of operator commands is appended to as they are applied
+    #[arg(long, global = true)]
+    audit: Option<String>,
+    /// Csv file the ledger entries of every client are appended to as they are posted
+    #[arg(long, global = true)]
+    journal: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> EngineResult<()> {
     // tracing_subscriber::fmt::init();
 
-    let file = get_file_path()?;
+    let args = Args::parse();
+
+    match &args.mode {
+        Some(Mode::Serve { addr }) => serve(&args, addr).await,
+        Some(Mode::Statement { client, file }) => statement(&args, ClientId(*client), file).await,
+        None => run(&args).await,
+    }
+}
+
+async fn run(args: &Args) -> EngineResult<()> {
+    let file = args.file.clone().ok_or(EngineError::InputNotProvided())?;
+
+    let mut engine = configure(args)?;
+
+    let (rejections, rejects) = match &args.rejects {
+        Some(path) => {
+            let (sender, receiver) = mpsc::unbounded_channel();
+            engine = engine.with_rejections(sender.clone());
+            (Some(sender), Some(spawn_rejects_writer(path, receiver)?))
+        }
+        None => (None, None),
+    };
+
+    let (engine, audit) = audit(args, engine)?;
+    let (engine, journal) = journal(args, engine)?;
+    let mut engine = prepare(args, engine).await?;
+
+    read_transactions(args, &file, &mut engine, rejections.as_ref()).await?;
+    apply_admin(args, &mut engine).await?;
 
+    finish(args, &mut engine).await?;
+
+    let report = engine.report().await?.sort_by(args.sort.into());
+
+    ReportFormat::from(args.output).write(&report, io::stdout().lock())?;
+
+    drop(rejections);
+
+    join(rejects).await?;
+    join(audit).await?;
+    join(journal).await
+}
+
+async fn statement(args: &Args, client: ClientId, file: &str) -> EngineResult<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let (engine, audit) = audit(args, configure(args)?.with_statement(client, sender))?;
+    let (engine, journal) = journal(args, engine)?;
+    let mut engine = prepare(args, engine).await?;
+
+    read_transactions(args, file, &mut engine, None).await?;
+    apply_admin(args, &mut engine).await?;
+
+    finish(args, &mut engine).await?;
+
+    // Workers send the last lines before they stop.
+    engine.report().await?;
+
+    let mut writer = csv::Writer::from_writer(io::stdout().lock());
+
+    while let Some(line) = receiver.recv().await {
+        writer.serialize(line)?;
+    }
+
+    writer.flush()?;
+
+    join(audit).await?;
+    join(journal).await
+}
+
+async fn read_transactions(
+    args: &Args,
+    file: &str,
+    engine: &mut PaymentEngine,
+    rejections: Option<&mpsc::UnboundedSender<Rejection>>,
+) -> EngineResult<()> {
     info!("Fetching {} file...", file);
 
-    let mut reader = CsvReader::new(&file)?;
-    let mut engine = PaymentEngine::default();
+    let format = args.format.unwrap_or_else(|| InputFormat::detect(file));
+    let path = file.to_string();
 
-    while let Some(result) = reader.next() {
-        match result {
-            Ok(transaction) => {
-                engine.process(transaction).await?;
-            }
+    let open = move || -> EngineResult<Box<dyn InputReader + Send>> {
+        let source: Box<dyn Read + Send> = if path == "-" {
+            Box::new(io::stdin())
+        } else {
+            Box::new(File::open(&path)?)
+        };
+
+        Ok(match format {
+            InputFormat::Csv => Box::new(CsvReader::from_reader(source)?),
+            InputFormat::Jsonl => Box::new(JsonLinesReader::from_reader(source)?),
+        })
+    };
+
+    let mut transactions = blocking::open(open, READ_AHEAD_SIZE).await?;
+
+    while let Some(ReadRecord {
+        transaction,
+        origin,
+    }) = transactions.next().await
+    {
+        match transaction {
+            Ok(transaction) => match engine.process_from(transaction, origin).await {
+                Err(EngineError::InternalError()) => return Err(EngineError::InternalError()),
+                Err(error) => warn!(?error, "Transaction has been rejected"),
+                Ok(()) => {}
+            },
             Err(error) => {
                 warn!(?error, "Cannot deserialize transaction");
+
+                if let Some(rejections) = rejections {
+                    let rejection = Rejection::new(origin, None, None, &error);
+                    rejections
+                        .send(rejection)
+                        .map_err(|_| EngineError::InternalError())?;
+                }
             }
         }
     }
 
-    println!("{}", engine.report().await?);
+    Ok(())
+}
+
+async fn serve(args: &Args, addr: &str) -> EngineResult<()> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let (engine, audit) = audit(args, configure(args)?.with_rejections(sender))?;
+    let (engine, journal) = journal(args, engine)?;
+    let mut engine = prepare(args, engine).await?;
+
+    apply_admin(args, &mut engine).await?;
+
+    let state = ApiState::new(engine, receiver);
+
+    let listener = TcpListener::bind(addr).await?;
+
+    info!("Serving on {}...", addr);
+
+    http::serve(listener, state.clone(), async {
+        tokio::signal::ctrl_c().await.unwrap_or_else(|error| {
+            warn!(?error, "Cannot listen for shutdown signal");
+        });
+    })
+    .await?;
+
+    finish(args, &mut state.into_engine()?).await?;
+
+    join(audit).await?;
+    join(journal).await
+}
+
+fn configure(args: &Args) -> EngineResult<PaymentEngine> {
+    let config = match &args.config {
+        Some(path) => EngineConfig::load(path)?,
+        None => EngineConfig::default(),
+    };
+
+    let mut builder = PaymentEngineBuilder::from_config(config);
+
+    if let Some(workers) = args.workers {
+        builder = builder.workers(workers);
+    }
+
+    if let Some(buffer) = args.buffer {
+        builder = builder.buffer(buffer);
+    }
+
+    if let Some(precision) = args.precision {
+        builder = builder.precision(precision);
+    }
+
+    if args.allow_redispute {
+        builder = builder.allow_redispute(true);
+    }
+
+    if args.ignore_duplicates {
+        builder = builder.duplicates(DuplicatePolicy::Ignore);
+    }
+
+    if args.restart_workers {
+        builder = builder.supervision(Supervision::Restart);
+    }
+
+    if let Some(client) = args.house_account {
+        builder = builder.house_account(ClientId(client));
+    }
+
+    builder.build()
+}
+
+/// Restores the engine state.
+async fn prepare(args: &Args, mut engine: PaymentEngine) -> EngineResult<PaymentEngine> {
+    if let Some(snapshot) = &args.snapshot {
+        info!("Restoring {} snapshot...", snapshot);
+
+        engine = engine.restore(EngineSnapshot::load(snapshot)?)?;
+    }
+
+    if let Some(wal) = &args.wal {
+        info!("Recovering {} write-ahead log...", wal);
+
+        engine = engine.recover(wal).await?;
+    }
+
+    Ok(engine)
+}
+
+/// Operator commands are applied once the transactions are read, so they can also unlock
+/// accounts frozen by a chargeback of the same run.
+async fn apply_admin(args: &Args, engine: &mut PaymentEngine) -> EngineResult<()> {
+    if let Some(admin) = &args.admin {
+        info!("Fetching {} admin file...", admin);
+
+        for command in AdminReader::new(admin)? {
+            match engine.admin(command?).await {
+                Err(EngineError::InternalError()) => return Err(EngineError::InternalError()),
+                Err(error) => warn!(?error, "Admin command has been rejected"),
+                Ok(()) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Saves the engine state.
+async fn finish(args: &Args, engine: &mut PaymentEngine) -> EngineResult<()> {
+    if let Some(checkpoint) = &args.checkpoint {
+        engine.checkpoint(checkpoint).await?;
+    }
 
     Ok(())
 }
 
-fn get_file_path() -> EngineResult<String> {
-    if let Some(file) = env::args().nth(1) {
-        Ok(file)
-    } else {
-        Err(EngineError::InputNotProvided())
+fn spawn_rejects_writer(
+    path: &str,
+    mut receiver: mpsc::UnboundedReceiver<Rejection>,
+) -> EngineResult<JoinHandle<EngineResult<()>>> {
+    let mut writer = RejectsWriter::create(path)?;
+
+    Ok(tokio::spawn(async move {
+        while let Some(rejection) = receiver.recv().await {
+            writer.write(&rejection)?;
+        }
+
+        writer.flush()
+    }))
+}
+
+/// The audit writer finishes once the engine, which holds its channel, is dropped.
+fn audit(
+    args: &Args,
+    engine: PaymentEngine,
+) -> EngineResult<(PaymentEngine, Option<JoinHandle<EngineResult<()>>>)> {
+    let Some(path) = &args.audit else {
+        return Ok((engine, None));
+    };
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let writer = spawn_audit_writer(path, receiver)?;
+
+    Ok((engine.with_audit(sender), Some(writer)))
+}
+
+fn spawn_audit_writer(
+    path: &str,
+    mut receiver: mpsc::UnboundedReceiver<AuditEntry>,
+) -> EngineResult<JoinHandle<EngineResult<()>>> {
+    let mut writer = AuditWriter::open(path)?;
+
+    Ok(tokio::spawn(async move {
+        while let Some(entry) = receiver.recv().await {
+            writer.write(&entry)?;
+        }
+
+        Ok(())
+    }))
+}
+
+/// The journal writer finishes once every worker, which holds its channel, has stopped.
+fn journal(
+    args: &Args,
+    engine: PaymentEngine,
+) -> EngineResult<(PaymentEngine, Option<JoinHandle<EngineResult<()>>>)> {
+    let Some(path) = &args.journal else {
+        return Ok((engine, None));
+    };
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let writer = spawn_journal_writer(path, receiver)?;
+
+    Ok((engine.with_journal(sender), Some(writer)))
+}
+
+fn spawn_journal_writer(
+    path: &str,
+    mut receiver: mpsc::UnboundedReceiver<JournalEntry>,
+) -> EngineResult<JoinHandle<EngineResult<()>>> {
+    let mut writer = JournalWriter::open(path)?;
+
+    Ok(tokio::spawn(async move {
+        while let Some(entry) = receiver.recv().await {
+            writer.write(&entry)?;
+        }
+
+        writer.flush()
+    }))
+}
+
+async fn join(writer: Option<JoinHandle<EngineResult<()>>>) -> EngineResult<()> {
+    match writer {
+        Some(writer) => writer.await.map_err(|_| EngineError::InternalError())?,
+        None => Ok(()),
     }
 }
@@ -1,10 +1,65 @@
+use crate::model::asset::Asset;
 use crate::model::client::ClientId;
+use crate::model::trade::TransactionId;
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
 
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LockReason {
+    Chargeback(TransactionId),
+    Admin { operator: String, reason: String },
+}
+
+impl fmt::Display for LockReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockReason::Chargeback(trade) => write!(f, "chargeback of transaction {}", trade),
+            LockReason::Admin { operator, reason } => write!(f, "{} (by {})", reason, operator),
+        }
+    }
+}
+
+/// Amounts are reported with a fixed scale, so `2` and `2.0` are both `2.0000`.
+pub const AMOUNT_SCALE: u32 = 4;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
 pub struct Account {
     pub client: ClientId,
+    #[serde(rename = "currency", skip_serializing_if = "Asset::is_default")]
+    pub asset: Asset,
+    #[serde(serialize_with = "fixed_scale")]
     pub available: Decimal,
+    #[serde(serialize_with = "fixed_scale")]
     pub held: Decimal,
+    #[serde(serialize_with = "fixed_scale")]
     pub total: Decimal,
+    /// Fees paid in this asset, skipped when nothing was charged.
+    #[serde(
+        serialize_with = "fixed_scale",
+        skip_serializing_if = "Decimal::is_zero"
+    )]
+    pub fees: Decimal,
     pub locked: bool,
+    #[serde(serialize_with = "describe")]
+    pub lock_reason: Option<LockReason>,
+}
+
+pub(crate) fn to_scale(mut amount: Decimal) -> Decimal {
+    amount.rescale(AMOUNT_SCALE);
+    amount
+}
+
+pub(crate) fn fixed_scale<S: Serializer>(
+    amount: &Decimal,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Serialize::serialize(&to_scale(*amount), serializer)
+}
+
+fn describe<S: Serializer>(reason: &Option<LockReason>, serializer: S) -> Result<S::Ok, S::Error> {
+    match reason {
+        Some(reason) => serializer.collect_str(reason),
+        None => serializer.serialize_none(),
+    }
 }
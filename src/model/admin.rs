@@ -0,0 +1,34 @@
+use crate::model::client::ClientId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminAction {
+    Lock,
+    Unlock,
+}
+
+impl fmt::Display for AdminAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminAction::Lock => write!(f, "lock"),
+            AdminAction::Unlock => write!(f, "unlock"),
+        }
+    }
+}
+
+/// Operation issued by an operator, never accepted from the client transactions stream.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AdminCommand {
+    pub action: AdminAction,
+    pub client: ClientId,
+    pub operator: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub command: AdminCommand,
+    pub accepted: bool,
+}
@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Currency or other asset a balance is kept in, e.g. `USD` or `BTC`.
+/// Transactions without a currency use the default, unnamed asset.
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Asset(pub String);
+
+impl Asset {
+    pub fn new(name: &str) -> Self {
+        Self(name.to_string())
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for Asset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,48 @@
+use crate::model::asset::Asset;
+use crate::model::client::ClientId;
+use crate::model::trade::TransactionId;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Named accounts of a client ledger. Funds of the client are split between `Available`,
+/// `Held` and `Reserved`, `External` is the counterpart of money entering or leaving the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerAccount {
+    Available,
+    Held,
+    /// Outgoing transfers waiting to be committed or released.
+    Reserved,
+    /// Fees paid by the client.
+    Fees,
+    External,
+}
+
+/// Operation a journal entry has been posted for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    Deposit,
+    Withdrawal,
+    Dispute,
+    Resolve,
+    Chargeback,
+    Reserve,
+    Commit,
+    Release,
+    Credit,
+    Fee,
+}
+
+/// Moves `amount` from the `debit` account to the `credit` account, so every entry is balanced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub client: ClientId,
+    pub tx: TransactionId,
+    pub kind: EntryKind,
+    #[serde(rename = "currency")]
+    pub asset: Asset,
+    pub debit: LedgerAccount,
+    pub credit: LedgerAccount,
+    pub amount: Decimal,
+}
@@ -1,4 +1,9 @@
 pub mod account;
+pub mod admin;
+pub mod asset;
 pub mod client;
+pub mod ledger;
+pub mod rejection;
 pub mod report;
+pub mod statement;
 pub mod trade;
@@ -0,0 +1,48 @@
+use crate::errors::EngineError;
+use crate::model::client::ClientId;
+use crate::model::trade::TransactionId;
+use serde::Serialize;
+
+/// Position of a transaction in its input source.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Origin {
+    pub line: u64,
+    pub row: String,
+}
+
+/// Transaction which has not been applied, either because it could not be parsed
+/// or because it has been refused by the engine.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct Rejection {
+    pub line: Option<u64>,
+    pub client: Option<ClientId>,
+    pub tx: Option<TransactionId>,
+    pub code: u16,
+    pub error: &'static str,
+    pub message: String,
+    pub row: Option<String>,
+}
+
+impl Rejection {
+    pub fn new(
+        origin: Option<Origin>,
+        client: Option<ClientId>,
+        tx: Option<TransactionId>,
+        error: &EngineError,
+    ) -> Self {
+        let (line, row) = match origin {
+            Some(origin) => (Some(origin.line), Some(origin.row)),
+            None => (None, None),
+        };
+
+        Self {
+            line,
+            client,
+            tx,
+            code: error.code(),
+            error: error.kind(),
+            message: error.to_string(),
+            row,
+        }
+    }
+}
@@ -1,28 +1,153 @@
-use crate::model::account::Account;
+use crate::model::account::{Account, LockReason, to_scale};
+use crate::model::asset::Asset;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
 use std::fmt;
 
+/// Key the report rows are sorted by. Amount keys sort ascending, ties are ordered by client
+/// and then by asset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportOrder {
+    #[default]
+    Client,
+    Available,
+    Held,
+    Total,
+}
+
+#[derive(Serialize)]
+#[serde(transparent)]
 pub struct Report {
     accounts: Vec<Account>,
 }
 
 impl Report {
+    /// One row per client and asset, sorted by client, so the same state always gives the same report.
     pub fn new(accounts: Vec<Account>) -> Self {
-        Self { accounts }
+        Self { accounts }.sort_by(ReportOrder::Client)
+    }
+
+    pub fn sort_by(mut self, order: ReportOrder) -> Self {
+        self.accounts.sort_by(|left, right| {
+            let key = match order {
+                ReportOrder::Client => left.client.cmp(&right.client),
+                ReportOrder::Available => left.available.cmp(&right.available),
+                ReportOrder::Held => left.held.cmp(&right.held),
+                ReportOrder::Total => left.total.cmp(&right.total),
+            };
+
+            key.then(left.client.cmp(&right.client))
+                .then(left.asset.cmp(&right.asset))
+        });
+
+        self
+    }
+
+    pub fn accounts(&self) -> &[Account] {
+        &self.accounts
+    }
+
+    /// Fees paid by all clients, per asset.
+    pub fn fees(&self) -> BTreeMap<Asset, Decimal> {
+        let mut fees = BTreeMap::new();
+
+        for account in self
+            .accounts
+            .iter()
+            .filter(|account| !account.fees.is_zero())
+        {
+            *fees.entry(account.asset.clone()).or_default() += account.fees;
+        }
+
+        fees
+    }
+
+    fn has_assets(&self) -> bool {
+        self.accounts
+            .iter()
+            .any(|account| !account.asset.is_default())
+    }
+
+    fn has_fees(&self) -> bool {
+        self.accounts.iter().any(|account| !account.fees.is_zero())
+    }
+
+    /// A chargeback lock is already told by `locked`, only operators give a reason of their own.
+    fn has_lock_reasons(&self) -> bool {
+        self.accounts
+            .iter()
+            .any(|account| matches!(account.lock_reason, Some(LockReason::Admin { .. })))
     }
 }
 
 impl fmt::Display for Report {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "client,available,held,total,locked")?;
+        // Reports of single-currency input without fees or operator locks keep the original columns.
+        let (assets, fees, reasons) = (self.has_assets(), self.has_fees(), self.has_lock_reasons());
+
+        write!(f, "client,")?;
+
+        if assets {
+            write!(f, "currency,")?;
+        }
+
+        write!(f, "available,held,total,")?;
+
+        if fees {
+            write!(f, "fees,")?;
+        }
+
+        write!(f, "locked")?;
+
+        if reasons {
+            write!(f, ",lock_reason")?;
+        }
+
+        writeln!(f)?;
 
         for account in &self.accounts {
-            writeln!(
+            write!(f, "{},", account.client)?;
+
+            if assets {
+                write!(f, "{},", escape(&account.asset.0))?;
+            }
+
+            write!(
                 f,
-                "{},{},{},{},{}",
-                account.client, account.available, account.held, account.total, account.locked
-            )?
+                "{},{},{},",
+                to_scale(account.available),
+                to_scale(account.held),
+                to_scale(account.total),
+            )?;
+
+            if fees {
+                write!(f, "{},", to_scale(account.fees))?;
+            }
+
+            write!(f, "{}", account.locked)?;
+
+            if reasons {
+                let reason = account
+                    .lock_reason
+                    .as_ref()
+                    .map(|reason| escape(&reason.to_string()))
+                    .unwrap_or_default();
+
+                write!(f, ",{}", reason)?;
+            }
+
+            writeln!(f)?;
         }
 
         Ok(())
     }
 }
+
+fn escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,125 @@
+use crate::errors::EngineError;
+use crate::model::account::{Account, fixed_scale};
+use crate::model::asset::Asset;
+use crate::model::rejection::Origin;
+use crate::model::trade::{Transaction, TransactionId};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepKind {
+    Deposit,
+    Withdrawal,
+    Dispute,
+    Resolve,
+    Chargeback,
+    /// Outgoing transfer.
+    Transfer,
+    /// Incoming transfer or fee credited to the house account.
+    Credit,
+    Lock,
+    Unlock,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepStatus {
+    Applied,
+    Rejected,
+}
+
+/// What happened to the client in a single step, before the outcome is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub kind: StepKind,
+    pub tx: Option<TransactionId>,
+    pub asset: Asset,
+    pub amount: Option<Decimal>,
+}
+
+impl From<&Transaction> for Step {
+    /// Disputes, resolves and chargebacks are reported in the default asset,
+    /// the worker replaces it with the asset of the disputed transaction.
+    fn from(tx: &Transaction) -> Self {
+        let (kind, asset, amount) = match tx {
+            Transaction::Deposit { asset, amount, .. } => (StepKind::Deposit, asset, Some(amount)),
+            Transaction::Withdrawal { asset, amount, .. } => {
+                (StepKind::Withdrawal, asset, Some(amount))
+            }
+            Transaction::Transfer { asset, amount, .. } => {
+                (StepKind::Transfer, asset, Some(amount))
+            }
+            Transaction::Dispute { .. } => (StepKind::Dispute, &Asset::default(), None),
+            Transaction::Resolve { .. } => (StepKind::Resolve, &Asset::default(), None),
+            Transaction::Chargeback { .. } => (StepKind::Chargeback, &Asset::default(), None),
+        };
+
+        Self {
+            kind,
+            tx: Some(tx.trade_id()),
+            asset: asset.clone(),
+            amount: amount.copied(),
+        }
+    }
+}
+
+/// Line of a client statement, with the balances in the asset of the step once it was handled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatementLine {
+    pub line: Option<u64>,
+    #[serde(rename = "type")]
+    pub kind: StepKind,
+    pub tx: Option<TransactionId>,
+    #[serde(rename = "currency")]
+    pub asset: Asset,
+    pub amount: Option<Decimal>,
+    pub status: StepStatus,
+    pub error: Option<String>,
+    #[serde(serialize_with = "fixed_scale")]
+    pub available: Decimal,
+    #[serde(serialize_with = "fixed_scale")]
+    pub held: Decimal,
+    #[serde(serialize_with = "fixed_scale")]
+    pub total: Decimal,
+    pub locked: bool,
+}
+
+impl StatementLine {
+    /// `account` holds the balances after the step, `None` for a client without any.
+    pub fn new(
+        step: Step,
+        origin: Option<&Origin>,
+        error: Option<&EngineError>,
+        account: Option<&Account>,
+    ) -> Self {
+        let (available, held, total, locked) = account.map_or(
+            (Decimal::ZERO, Decimal::ZERO, Decimal::ZERO, false),
+            |account| {
+                (
+                    account.available,
+                    account.held,
+                    account.total,
+                    account.locked,
+                )
+            },
+        );
+
+        Self {
+            line: origin.map(|origin| origin.line),
+            kind: step.kind,
+            tx: step.tx,
+            asset: step.asset,
+            amount: step.amount,
+            status: match error {
+                Some(_) => StepStatus::Rejected,
+                None => StepStatus::Applied,
+            },
+            error: error.map(EngineError::to_string),
+            available,
+            held,
+            total,
+            locked,
+        }
+    }
+}
@@ -1,9 +1,10 @@
+use crate::model::asset::Asset;
 use crate::model::client::ClientId;
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub struct TransactionId(pub u32);
 
 impl fmt::Display for TransactionId {
@@ -12,21 +13,49 @@ impl fmt::Display for TransactionId {
     }
 }
 
-#[derive(Debug, Eq, PartialEq)]
+/// Seconds since the unix epoch.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct Timestamp(pub u64);
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Transaction {
     Deposit {
         client: ClientId,
         trade: TransactionId,
         amount: Decimal,
+        #[serde(default)]
+        asset: Asset,
+        #[serde(default)]
+        timestamp: Option<Timestamp>,
     },
     Withdrawal {
         client: ClientId,
         trade: TransactionId,
         amount: Decimal,
+        #[serde(default)]
+        asset: Asset,
+        #[serde(default)]
+        timestamp: Option<Timestamp>,
+    },
+    Transfer {
+        client: ClientId,
+        to: ClientId,
+        trade: TransactionId,
+        amount: Decimal,
+        #[serde(default)]
+        asset: Asset,
     },
     Dispute {
         client: ClientId,
         trade: TransactionId,
+        #[serde(default)]
+        timestamp: Option<Timestamp>,
     },
     Resolve {
         client: ClientId,
@@ -43,9 +72,59 @@ impl Transaction {
         match self {
             Transaction::Deposit { client, .. } => *client,
             Transaction::Withdrawal { client, .. } => *client,
+            Transaction::Transfer { client, .. } => *client,
             Transaction::Dispute { client, .. } => *client,
             Transaction::Resolve { client, .. } => *client,
             Transaction::Chargeback { client, .. } => *client,
         }
     }
+
+    pub fn trade_id(&self) -> TransactionId {
+        match self {
+            Transaction::Deposit { trade, .. } => *trade,
+            Transaction::Withdrawal { trade, .. } => *trade,
+            Transaction::Transfer { trade, .. } => *trade,
+            Transaction::Dispute { trade, .. } => *trade,
+            Transaction::Resolve { trade, .. } => *trade,
+            Transaction::Chargeback { trade, .. } => *trade,
+        }
+    }
+
+    /// Client credited by a transfer.
+    pub fn recipient(&self) -> Option<ClientId> {
+        match self {
+            Transaction::Transfer { to, .. } => Some(*to),
+            _ => None,
+        }
+    }
+
+    /// Amount moved by a deposit, withdrawal or transfer.
+    pub fn amount(&self) -> Option<Decimal> {
+        match self {
+            Transaction::Deposit { amount, .. } => Some(*amount),
+            Transaction::Withdrawal { amount, .. } => Some(*amount),
+            Transaction::Transfer { amount, .. } => Some(*amount),
+            _ => None,
+        }
+    }
+
+    /// Time the deposit, withdrawal or dispute happened at, when the source provides it.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Transaction::Deposit { timestamp, .. } => *timestamp,
+            Transaction::Withdrawal { timestamp, .. } => *timestamp,
+            Transaction::Dispute { timestamp, .. } => *timestamp,
+            _ => None,
+        }
+    }
+
+    /// Deposits, withdrawals and transfers create a new transaction, others refer to an existing one.
+    pub fn is_new_trade(&self) -> bool {
+        matches!(
+            self,
+            Transaction::Deposit { .. }
+                | Transaction::Withdrawal { .. }
+                | Transaction::Transfer { .. }
+        )
+    }
 }
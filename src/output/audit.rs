@@ -0,0 +1,41 @@
+use crate::errors::EngineResult;
+use crate::model::admin::AuditEntry;
+use std::fs::{File, OpenOptions};
+
+/// Appends the audit trail to a csv file, every entry is flushed as soon as it is written,
+/// so the trail survives a crash of the run. The header is written only to an empty file.
+pub struct AuditWriter {
+    writer: csv::Writer<File>,
+}
+
+impl AuditWriter {
+    pub fn open(path: &str) -> EngineResult<AuditWriter> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+
+        let mut writer = csv::Writer::from_writer(file);
+
+        if empty {
+            writer.write_record(["action", "client", "operator", "reason", "accepted"])?;
+            writer.flush()?;
+        }
+
+        Ok(AuditWriter { writer })
+    }
+
+    pub fn write(&mut self, entry: &AuditEntry) -> EngineResult<()> {
+        let command = &entry.command;
+
+        self.writer.write_record([
+            command.action.to_string(),
+            command.client.to_string(),
+            command.operator.clone(),
+            command.reason.clone(),
+            entry.accepted.to_string(),
+        ])?;
+
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::errors::EngineResult;
+use crate::model::ledger::JournalEntry;
+use std::fs::{File, OpenOptions};
+
+/// Appends posted ledger entries to a csv file as they are streamed by the workers,
+/// so the journal of every run continues the previous one. The header is written only
+/// to an empty file.
+pub struct JournalWriter {
+    writer: csv::Writer<File>,
+}
+
+impl JournalWriter {
+    pub fn open(path: &str) -> EngineResult<JournalWriter> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let empty = file.metadata()?.len() == 0;
+
+        let writer = csv::WriterBuilder::new()
+            .has_headers(empty)
+            .from_writer(file);
+
+        Ok(JournalWriter { writer })
+    }
+
+    pub fn write(&mut self, entry: &JournalEntry) -> EngineResult<()> {
+        self.writer.serialize(entry)?;
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> EngineResult<()> {
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
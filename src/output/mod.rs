@@ -0,0 +1,4 @@
+pub mod audit;
+pub mod journal;
+pub mod rejects;
+pub mod report;
@@ -0,0 +1,47 @@
+use crate::errors::EngineResult;
+use crate::model::rejection::Rejection;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Writes rejections as csv, or as json lines when the file has `json`, `jsonl` or `ndjson` extension.
+pub enum RejectsWriter {
+    Csv(Box<csv::Writer<File>>),
+    Json(BufWriter<File>),
+}
+
+impl RejectsWriter {
+    pub fn create(path: &str) -> EngineResult<RejectsWriter> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str());
+
+        match extension {
+            Some("json" | "jsonl" | "ndjson") => {
+                Ok(RejectsWriter::Json(BufWriter::new(File::create(path)?)))
+            }
+            _ => Ok(RejectsWriter::Csv(Box::new(csv::Writer::from_path(path)?))),
+        }
+    }
+
+    pub fn write(&mut self, rejection: &Rejection) -> EngineResult<()> {
+        match self {
+            RejectsWriter::Csv(writer) => writer.serialize(rejection)?,
+            RejectsWriter::Json(writer) => {
+                serde_json::to_writer(&mut *writer, rejection)?;
+                writeln!(writer)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> EngineResult<()> {
+        match self {
+            RejectsWriter::Csv(writer) => writer.flush()?,
+            RejectsWriter::Json(writer) => writer.flush()?,
+        }
+
+        Ok(())
+    }
+}
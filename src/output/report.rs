@@ -0,0 +1,34 @@
+use crate::errors::EngineResult;
+use crate::model::report::Report;
+use std::io::Write;
+
+/// Output format of the final report. `Json` writes a single array of accounts,
+/// `Ndjson` one account object per line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl ReportFormat {
+    pub fn write<W: Write>(&self, report: &Report, mut writer: W) -> EngineResult<()> {
+        match self {
+            ReportFormat::Csv => write!(writer, "{}", report)?,
+            ReportFormat::Json => {
+                serde_json::to_writer(&mut writer, report)?;
+                writeln!(writer)?;
+            }
+            ReportFormat::Ndjson => {
+                for account in report.accounts() {
+                    serde_json::to_writer(&mut writer, account)?;
+                    writeln!(writer)?;
+                }
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+}
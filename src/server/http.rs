@@ -0,0 +1,275 @@
+use crate::core::engine::PaymentEngine;
+use crate::core::query::EngineQueries;
+use crate::errors::{EngineError, EngineResult, ErrorCategory};
+use crate::input::json;
+use crate::model::asset::Asset;
+use crate::model::client::ClientId;
+use crate::model::rejection::{Origin, Rejection};
+use crate::model::trade::TransactionId;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, mpsc};
+
+/// Number of tx ids whose rejections are kept by default.
+pub const REJECTIONS_CAPACITY: usize = 100_000;
+
+/// Rejections of the most recently rejected tx ids, the oldest tx id is dropped once
+/// `capacity` of them are kept.
+struct RejectionLog {
+    capacity: usize,
+    rejections: HashMap<TransactionId, Vec<Rejection>>,
+    order: VecDeque<TransactionId>,
+}
+
+impl RejectionLog {
+    fn new(capacity: usize) -> RejectionLog {
+        RejectionLog {
+            capacity,
+            rejections: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, tx: TransactionId, rejection: Rejection) {
+        if let Some(rejections) = self.rejections.get_mut(&tx) {
+            rejections.push(rejection);
+            return;
+        }
+
+        while self.order.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => self.rejections.remove(&oldest),
+                None => break,
+            };
+        }
+
+        if self.capacity > 0 {
+            self.order.push_back(tx);
+            self.rejections.insert(tx, vec![rejection]);
+        }
+    }
+
+    fn get(&self, tx: &TransactionId) -> Option<&Vec<Rejection>> {
+        self.rejections.get(tx)
+    }
+}
+
+/// Engine shared by the http handlers, with the rejections it reported kept by tx id.
+/// Submissions take the engine lock, queries go to the workers without it.
+pub struct ApiState {
+    engine: Mutex<PaymentEngine>,
+    queries: EngineQueries,
+    rejections: Arc<Mutex<RejectionLog>>,
+}
+
+/// Result of a submitted transaction, `accepted` once it has been applied to the wallet.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Outcome {
+    Accepted { tx: TransactionId },
+    Rejected(Rejection),
+}
+
+impl ApiState {
+    /// The receiver has to be the one the engine reports rejections to.
+    pub fn new(engine: PaymentEngine, receiver: mpsc::UnboundedReceiver<Rejection>) -> Arc<Self> {
+        Self::with_capacity(engine, receiver, REJECTIONS_CAPACITY)
+    }
+
+    /// Keeps the rejections of at most `capacity` tx ids.
+    pub fn with_capacity(
+        engine: PaymentEngine,
+        mut receiver: mpsc::UnboundedReceiver<Rejection>,
+        capacity: usize,
+    ) -> Arc<Self> {
+        let rejections = Arc::new(Mutex::new(RejectionLog::new(capacity)));
+
+        let log = rejections.clone();
+        tokio::spawn(async move {
+            while let Some(rejection) = receiver.recv().await {
+                if let Some(tx) = rejection.tx {
+                    log.lock().await.push(tx, rejection);
+                }
+            }
+        });
+
+        Arc::new(Self {
+            queries: engine.queries(),
+            engine: Mutex::new(engine),
+            rejections,
+        })
+    }
+
+    /// Gives the engine back once the server has stopped.
+    pub fn into_engine(self: Arc<Self>) -> EngineResult<PaymentEngine> {
+        Arc::into_inner(self)
+            .map(|state| state.engine.into_inner())
+            .ok_or(EngineError::InternalError())
+    }
+
+    /// Queues the whole batch under a single engine lock, outcomes are awaited afterwards.
+    async fn submit(&self, items: Vec<Value>) -> EngineResult<Vec<Outcome>> {
+        let mut queued = Vec::with_capacity(items.len());
+
+        let mut engine = self.engine.lock().await;
+
+        for (index, item) in items.into_iter().enumerate() {
+            let origin = Origin {
+                line: index as u64 + 1,
+                row: item.to_string(),
+            };
+
+            match json::parse(&origin.row) {
+                Ok(transaction) => {
+                    let (client, tx) = (transaction.client_id(), transaction.trade_id());
+                    let ack = engine
+                        .submit_from(transaction, Some(origin.clone()))
+                        .await?;
+                    queued.push(Ok((origin, client, tx, ack)));
+                }
+                Err(error) => queued.push(Err(Rejection::new(Some(origin), None, None, &error))),
+            }
+        }
+
+        drop(engine);
+
+        let mut outcomes = Vec::with_capacity(queued.len());
+
+        for item in queued {
+            let outcome = match item {
+                Ok((origin, client, tx, ack)) => match ack.await {
+                    Ok(()) => Outcome::Accepted { tx },
+                    Err(EngineError::InternalError()) => return Err(EngineError::InternalError()),
+                    Err(error) => Outcome::Rejected(Rejection::new(
+                        Some(origin),
+                        Some(client),
+                        Some(tx),
+                        &error,
+                    )),
+                },
+                Err(rejection) => Outcome::Rejected(rejection),
+            };
+
+            outcomes.push(outcome);
+        }
+
+        Ok(outcomes)
+    }
+}
+
+/// `POST /transactions` takes a transaction object or an array of them,
+/// `GET /accounts` returns the report, `GET /accounts/{client}` a single account
+/// and `GET /rejections/{tx}` every rejection reported for the tx id.
+pub fn router(state: Arc<ApiState>) -> Router {
+    Router::new()
+        .route("/transactions", post(submit))
+        .route("/accounts", get(report))
+        .route("/accounts/{client}", get(account))
+        .route("/rejections/{tx}", get(rejections))
+        .with_state(state)
+}
+
+pub async fn serve(
+    listener: TcpListener,
+    state: Arc<ApiState>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> EngineResult<()> {
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    Ok(())
+}
+
+async fn submit(
+    State(state): State<Arc<ApiState>>,
+    Json(body): Json<Value>,
+) -> Result<Response, ApiError> {
+    match body {
+        Value::Array(items) => {
+            let outcomes = state.submit(items).await?;
+
+            Ok((StatusCode::OK, Json(outcomes)).into_response())
+        }
+        item => {
+            let outcome = state.submit(vec![item]).await?.pop();
+
+            match outcome {
+                Some(outcome @ Outcome::Accepted { .. }) => {
+                    Ok((StatusCode::OK, Json(outcome)).into_response())
+                }
+                Some(outcome @ Outcome::Rejected(_)) => {
+                    Ok((StatusCode::UNPROCESSABLE_ENTITY, Json(outcome)).into_response())
+                }
+                None => Err(EngineError::InternalError().into()),
+            }
+        }
+    }
+}
+
+async fn report(State(state): State<Arc<ApiState>>) -> Result<Response, ApiError> {
+    let report = state.queries.report().await?;
+
+    Ok(Json(report).into_response())
+}
+
+/// Balances in the default asset, or in the one given with `?currency=`.
+#[derive(Deserialize)]
+struct AccountQuery {
+    #[serde(default)]
+    currency: Asset,
+}
+
+async fn account(
+    State(state): State<Arc<ApiState>>,
+    Path(client): Path<ClientId>,
+    Query(query): Query<AccountQuery>,
+) -> Result<Response, ApiError> {
+    match state.queries.account_in(client, query.currency).await? {
+        Some(account) => Ok(Json(account).into_response()),
+        None => Ok(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+async fn rejections(State(state): State<Arc<ApiState>>, Path(tx): Path<TransactionId>) -> Response {
+    match state.rejections.lock().await.get(&tx) {
+        Some(rejections) => Json(rejections.clone()).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+struct ApiError(EngineError);
+
+impl From<EngineError> for ApiError {
+    fn from(error: EngineError) -> Self {
+        ApiError(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let error = self.0;
+
+        let body = Json(serde_json::json!({
+            "code": error.code(),
+            "error": error.kind(),
+            "message": error.to_string(),
+        }));
+
+        let status = match error.category() {
+            ErrorCategory::Business => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorCategory::Input => StatusCode::BAD_REQUEST,
+            ErrorCategory::Storage | ErrorCategory::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        (status, body).into_response()
+    }
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::{deposit, withdrawal};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::errors::EngineError;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+#[tokio::test]
+async fn acknowledge_applied_transaction() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    let ack = engine.submit(deposit(1, 1, dec!(2))).await?;
+
+    assert_eq!(ack.await, Ok(()));
+
+    let ack = engine.submit(withdrawal(1, 2, dec!(1.5))).await?;
+
+    assert_eq!(ack.await, Ok(()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn acknowledge_worker_rejection() -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let mut engine = PaymentEngine::new(2).with_rejections(sender);
+
+    engine.submit(deposit(1, 1, dec!(1))).await?.await?;
+
+    let ack = engine.submit(withdrawal(1, 2, dec!(5))).await?;
+
+    assert_eq!(ack.await, Err(EngineError::NotEnoughMany(TransactionId(2))));
+
+    let rejection = receiver.recv().await.unwrap();
+
+    assert_eq!(rejection.tx, Some(TransactionId(2)));
+    assert_eq!(rejection.error, "NotEnoughMany");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn acknowledge_engine_rejection() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine.submit(deposit(1, 1, dec!(1))).await?.await?;
+
+    let ack = engine.submit(deposit(2, 1, dec!(1))).await?;
+
+    assert_eq!(
+        ack.await,
+        Err(EngineError::DuplicateTransaction(TransactionId(1)))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn acknowledge_transfer() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine.submit(deposit(1, 1, dec!(3))).await?.await?;
+
+    let transfer = |trade, amount| Transaction::Transfer {
+        client: ClientId(1),
+        to: ClientId(2),
+        trade: TransactionId(trade),
+        amount,
+        asset: Asset::default(),
+    };
+
+    assert_eq!(engine.submit(transfer(2, dec!(2))).await?.await, Ok(()));
+    assert_eq!(
+        engine.submit(transfer(3, dec!(2))).await?.await,
+        Err(EngineError::NotEnoughMany(TransactionId(3)))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dropped_acknowledgement_does_not_stop_processing() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    drop(engine.submit(deposit(1, 1, dec!(1))).await?);
+
+    engine.submit(deposit(1, 2, dec!(1))).await?.await?;
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.total, dec!(2));
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use common::deposit;
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::wallet::AccountWallet;
+use payment_engine::errors::EngineError;
+use payment_engine::model::account::LockReason;
+use payment_engine::model::admin::{AdminAction, AdminCommand, AuditEntry};
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::TransactionId;
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+fn admin_lock() -> LockReason {
+    LockReason::Admin {
+        operator: String::from("ops"),
+        reason: String::from("suspicious activity"),
+    }
+}
+
+fn init_wallet(client_id: ClientId) -> anyhow::Result<AccountWallet> {
+    let mut wallet = AccountWallet::new(client_id);
+
+    let trade = TransactionId(1);
+    let amount = dec!(2);
+    wallet.deposit(trade, Asset::default(), amount)?;
+
+    wallet.dispute(trade)?;
+    wallet.chargeback(trade)?;
+
+    Ok(wallet)
+}
+
+#[test]
+fn unlock_charged_back_account() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let confirmation = wallet.unlock();
+
+    assert!(confirmation.is_ok());
+
+    let trade = TransactionId(2);
+    let amount = dec!(1.5);
+
+    let confirmation = wallet.deposit(trade, Asset::default(), amount);
+
+    assert!(confirmation.is_ok());
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert_eq!(account.available, dec!(1.5));
+    assert!(!account.locked);
+    assert_eq!(account.lock_reason, None);
+
+    Ok(())
+}
+
+#[test]
+fn unlock_not_locked_account() -> anyhow::Result<()> {
+    let mut wallet = AccountWallet::new(ClientId(1));
+
+    let confirmation = wallet.unlock();
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::NotLocked(ClientId(1))));
+
+    Ok(())
+}
+
+#[test]
+fn lock_account() -> anyhow::Result<()> {
+    let mut wallet = AccountWallet::new(ClientId(1));
+
+    let confirmation = wallet.lock(admin_lock());
+
+    assert!(confirmation.is_ok());
+
+    let trade = TransactionId(1);
+    let amount = dec!(1.5);
+
+    let confirmation = wallet.deposit(trade, Asset::default(), amount);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::FrozenAccount(ClientId(1))));
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert!(account.locked);
+    assert_eq!(account.lock_reason, Some(admin_lock()));
+
+    Ok(())
+}
+
+#[test]
+fn lock_already_locked_account() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let confirmation = wallet.lock(admin_lock());
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::FrozenAccount(ClientId(1))));
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert_eq!(
+        account.lock_reason,
+        Some(LockReason::Chargeback(TransactionId(1)))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn admin_commands_are_audited() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::default();
+
+    engine.process(deposit(1, 1, dec!(2))).await?;
+
+    let lock = AdminCommand {
+        action: AdminAction::Lock,
+        client: ClientId(1),
+        operator: String::from("ops"),
+        reason: String::from("suspicious activity"),
+    };
+
+    let unlock = AdminCommand {
+        action: AdminAction::Unlock,
+        client: ClientId(2),
+        operator: String::from("ops"),
+        reason: String::from("mistake"),
+    };
+
+    assert!(engine.admin(lock.clone()).await.is_ok());
+    assert_eq!(
+        engine.admin(unlock.clone()).await,
+        Err(EngineError::NotLocked(ClientId(2)))
+    );
+
+    assert_eq!(
+        engine.audit(),
+        &[
+            AuditEntry {
+                command: lock,
+                accepted: true,
+            },
+            AuditEntry {
+                command: unlock,
+                accepted: false,
+            },
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn audit_entries_are_sent_as_commands_are_applied() -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut engine = PaymentEngine::default().with_audit(sender);
+
+    let unlock = AdminCommand {
+        action: AdminAction::Unlock,
+        client: ClientId(1),
+        operator: String::from("ops"),
+        reason: String::from("mistake"),
+    };
+
+    assert!(receiver.try_recv().is_err());
+    assert!(engine.admin(unlock.clone()).await.is_err());
+
+    assert_eq!(
+        receiver.try_recv()?,
+        AuditEntry {
+            command: unlock,
+            accepted: false,
+        }
+    );
+
+    Ok(())
+}
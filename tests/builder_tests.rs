@@ -0,0 +1,119 @@
+mod common;
+
+use common::deposit;
+use payment_engine::core::builder::{EngineConfig, PaymentEngineBuilder};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::policy::DuplicatePolicy;
+use payment_engine::core::supervisor::Supervision;
+use payment_engine::core::wal::WalSync;
+use payment_engine::errors::EngineError;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::TransactionId;
+use rust_decimal_macros::dec;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+#[test]
+fn reject_invalid_options() {
+    let invalid = [
+        PaymentEngine::builder().workers(0),
+        PaymentEngine::builder().workers(65536),
+        PaymentEngine::builder().buffer(0),
+        PaymentEngine::builder().precision(5),
+        PaymentEngine::builder().wal_sync(WalSync::Batch(0)),
+    ];
+
+    for builder in invalid {
+        assert!(matches!(
+            builder.build().err(),
+            Some(EngineError::InvalidConfig(_))
+        ));
+    }
+}
+
+#[test]
+fn reject_invalid_pool_size() {
+    for pool_size in [0, 65536] {
+        assert!(matches!(
+            PaymentEngine::builder().workers(pool_size).build().err(),
+            Some(EngineError::InvalidConfig(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn build_with_precision_limit() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::builder()
+        .workers(1)
+        .buffer(1)
+        .precision(2)
+        .build()?;
+
+    assert_eq!(
+        engine.submit(deposit(1, 1, dec!(1.25))).await?.await,
+        Ok(())
+    );
+    assert_eq!(
+        engine.submit(deposit(1, 2, dec!(1.125))).await?.await,
+        Err(EngineError::InvalidPrecision(TransactionId(2)))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn build_with_ignored_duplicates() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::builder()
+        .duplicates(DuplicatePolicy::Ignore)
+        .build()?;
+
+    engine.process(deposit(1, 1, dec!(1))).await?;
+
+    assert_eq!(engine.submit(deposit(1, 1, dec!(1))).await?.await, Ok(()));
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.total, dec!(1));
+
+    Ok(())
+}
+
+#[test]
+fn load_config_file() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(file, "workers = 4")?;
+    writeln!(file, "duplicates = \"ignore\"")?;
+    writeln!(file, "supervision = \"restart\"")?;
+    writeln!(file, "wal_sync = {{ batch = 100 }}")?;
+
+    let config = EngineConfig::load(file.path().to_str().unwrap())?;
+
+    assert_eq!(
+        config,
+        EngineConfig {
+            workers: 4,
+            duplicates: DuplicatePolicy::Ignore,
+            supervision: Supervision::Restart,
+            wal_sync: WalSync::Batch(100),
+            ..EngineConfig::default()
+        }
+    );
+
+    assert!(PaymentEngineBuilder::from_config(config).build().is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn reject_unknown_config_option() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(file, "worker = 4")?;
+
+    let result = EngineConfig::load(file.path().to_str().unwrap());
+
+    assert!(matches!(result, Err(EngineError::InvalidConfig(_))));
+
+    Ok(())
+}
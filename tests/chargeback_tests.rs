@@ -1,6 +1,6 @@
 use payment_engine::core::wallet::AccountWallet;
 use payment_engine::errors::EngineError;
-use payment_engine::model::account::Account;
+use payment_engine::model::asset::Asset;
 use payment_engine::model::client::ClientId;
 use payment_engine::model::trade::TransactionId;
 use rust_decimal_macros::dec;
@@ -10,11 +10,11 @@ fn init_wallet(client_id: ClientId) -> anyhow::Result<AccountWallet> {
 
     let trade = TransactionId(1);
     let amount = dec!(2);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     let trade = TransactionId(2);
     let amount = dec!(5);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     let trade = TransactionId(2);
     wallet.dispute(trade)?;
@@ -22,6 +22,20 @@ fn init_wallet(client_id: ClientId) -> anyhow::Result<AccountWallet> {
     Ok(wallet)
 }
 
+#[test]
+fn chargeback_unexist_transaction() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(3);
+
+    let confirmation = wallet.chargeback(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::TransactionNotFound(trade)));
+
+    Ok(())
+}
+
 #[test]
 fn chargeback_unexist_dispute() -> anyhow::Result<()> {
     let mut wallet = init_wallet(ClientId(1))?;
@@ -31,7 +45,7 @@ fn chargeback_unexist_dispute() -> anyhow::Result<()> {
     let confirmation = wallet.chargeback(trade);
 
     assert!(confirmation.is_err());
-    assert_eq!(confirmation, Err(EngineError::TransactionNotFound(trade)));
+    assert_eq!(confirmation, Err(EngineError::NotDisputed(trade)));
 
     Ok(())
 }
@@ -46,7 +60,7 @@ fn chargeback_positive_amount() -> anyhow::Result<()> {
 
     assert!(confirmation.is_ok());
 
-    let account: Account = wallet.into();
+    let account = wallet.account(&Asset::default()).unwrap();
 
     assert_eq!(account.client, ClientId(1));
     assert_eq!(account.available, dec!(2));
@@ -70,10 +84,67 @@ fn deposit_locked_account() -> anyhow::Result<()> {
     let trade = TransactionId(3);
     let amount = dec!(1.5);
 
-    let confirmation = wallet.deposit(trade, amount);
+    let confirmation = wallet.deposit(trade, Asset::default(), amount);
 
     assert!(confirmation.is_err());
     assert_eq!(confirmation, Err(EngineError::FrozenAccount(ClientId(1))));
 
     Ok(())
 }
+
+#[test]
+fn chargeback_disputed_withdrawal() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(3);
+    let amount = dec!(1.5);
+
+    wallet.withdrawal(trade, Asset::default(), amount)?;
+    wallet.dispute(trade)?;
+
+    let confirmation = wallet.chargeback(trade);
+
+    assert!(confirmation.is_ok());
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert_eq!(account.client, ClientId(1));
+    assert_eq!(account.available, dec!(2));
+    assert_eq!(account.held, dec!(5));
+    assert_eq!(account.total, dec!(7));
+    assert!(account.locked);
+
+    Ok(())
+}
+
+#[test]
+fn chargeback_resolved_dispute() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(2);
+
+    wallet.resolve(trade)?;
+
+    let confirmation = wallet.chargeback(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::AlreadyResolved(trade)));
+
+    Ok(())
+}
+
+#[test]
+fn chargeback_already_charged_back() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(2);
+
+    wallet.chargeback(trade)?;
+
+    let confirmation = wallet.chargeback(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::AlreadyChargedBack(trade)));
+
+    Ok(())
+}
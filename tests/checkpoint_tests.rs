@@ -0,0 +1,155 @@
+mod common;
+
+use common::{deposit, dispute, report_rows};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::snapshot::EngineSnapshot;
+use payment_engine::errors::EngineError;
+use payment_engine::model::admin::{AdminAction, AdminCommand};
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use tempfile::TempDir;
+
+async fn init_checkpoint(path: &str) -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(3);
+
+    for (client, trade, amount) in [(1, 1, dec!(2)), (2, 2, dec!(5)), (3, 3, dec!(1.5))] {
+        engine.process(deposit(client, trade, amount)).await?;
+    }
+
+    engine.process(dispute(2, 2)).await?;
+
+    engine.checkpoint(path).await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,2.0000,0.0000,2.0000,false",
+            "2,0.0000,5.0000,5.0000,false",
+            "3,1.5000,0.0000,1.5000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_wallets_from_checkpoint() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("state.json");
+    let path = path.to_str().unwrap();
+
+    init_checkpoint(path).await?;
+
+    let engine = PaymentEngine::new(2).restore(EngineSnapshot::load(path)?)?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,2.0000,0.0000,2.0000,false",
+            "2,0.0000,5.0000,5.0000,false",
+            "3,1.5000,0.0000,1.5000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn process_disputes_from_previous_run() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("state.json");
+    let path = path.to_str().unwrap();
+
+    init_checkpoint(path).await?;
+
+    let mut engine = PaymentEngine::new(2).restore(EngineSnapshot::load(path)?)?;
+
+    engine
+        .process(Transaction::Chargeback {
+            client: ClientId(2),
+            trade: TransactionId(2),
+        })
+        .await?;
+
+    engine.process(dispute(1, 1)).await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,0.0000,2.0000,2.0000,false",
+            "2,0.0000,0.0000,0.0000,true",
+            "3,1.5000,0.0000,1.5000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_duplicates_from_previous_run() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("state.json");
+    let path = path.to_str().unwrap();
+
+    init_checkpoint(path).await?;
+
+    let mut engine = PaymentEngine::default().restore(EngineSnapshot::load(path)?)?;
+
+    let trade = TransactionId(3);
+
+    let confirmation = engine.process(deposit(4, trade.0, dec!(1))).await;
+
+    assert_eq!(confirmation, Err(EngineError::DuplicateTransaction(trade)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_audit_trail_from_checkpoint() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("state.json");
+    let path = path.to_str().unwrap();
+
+    let mut engine = PaymentEngine::default();
+
+    let command = AdminCommand {
+        action: AdminAction::Lock,
+        client: ClientId(1),
+        operator: String::from("ops"),
+        reason: String::from("fraud"),
+    };
+
+    engine.admin(command.clone()).await?;
+    engine.checkpoint(path).await?;
+
+    let engine = PaymentEngine::default().restore(EngineSnapshot::load(path)?)?;
+
+    assert_eq!(engine.audit().len(), 1);
+    assert_eq!(engine.audit()[0].command, command);
+    assert_eq!(
+        report_rows(engine).await?,
+        vec!["1,0.0000,0.0000,0.0000,true,fraud (by ops)"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restore_on_running_engine() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("state.json");
+    let path = path.to_str().unwrap();
+
+    init_checkpoint(path).await?;
+
+    let mut engine = PaymentEngine::new(2);
+
+    engine.process(deposit(1, 4, dec!(1))).await?;
+
+    let restored = engine.restore(EngineSnapshot::load(path)?);
+
+    assert!(matches!(restored, Err(EngineError::InvalidConfig(_))));
+
+    Ok(())
+}
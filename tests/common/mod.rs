@@ -0,0 +1,47 @@
+// Every test crate uses only some of the fixtures.
+#![allow(dead_code)]
+
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal::Decimal;
+
+pub fn deposit(client: u16, trade: u32, amount: Decimal) -> Transaction {
+    deposit_in(client, trade, amount, Asset::default())
+}
+
+pub fn deposit_in(client: u16, trade: u32, amount: Decimal, asset: Asset) -> Transaction {
+    Transaction::Deposit {
+        client: ClientId(client),
+        trade: TransactionId(trade),
+        amount,
+        asset,
+        timestamp: None,
+    }
+}
+
+pub fn withdrawal(client: u16, trade: u32, amount: Decimal) -> Transaction {
+    Transaction::Withdrawal {
+        client: ClientId(client),
+        trade: TransactionId(trade),
+        amount,
+        asset: Asset::default(),
+        timestamp: None,
+    }
+}
+
+pub fn dispute(client: u16, trade: u32) -> Transaction {
+    Transaction::Dispute {
+        client: ClientId(client),
+        trade: TransactionId(trade),
+        timestamp: None,
+    }
+}
+
+/// Rows of the final report without its header.
+pub async fn report_rows(engine: PaymentEngine) -> anyhow::Result<Vec<String>> {
+    let report = engine.report().await?.to_string();
+
+    Ok(report.lines().skip(1).map(String::from).collect())
+}
@@ -1,6 +1,10 @@
+mod common;
+
 use payment_engine::input::csv::CsvReader;
 use payment_engine::input::reader::InputReader;
+use payment_engine::model::asset::Asset;
 use payment_engine::model::client::ClientId;
+use payment_engine::model::rejection::Origin;
 use payment_engine::model::trade::{Transaction, TransactionId};
 use rust_decimal::Decimal;
 use std::io::Write;
@@ -20,33 +24,13 @@ fn read_all_transaction_types_from_file() -> anyhow::Result<()> {
     let mut reader = CsvReader::from_file(file.reopen()?)?;
 
     let deposit = reader.next().unwrap()?;
-    assert_eq!(
-        deposit,
-        Transaction::Deposit {
-            client: ClientId(1),
-            trade: TransactionId(1),
-            amount: Decimal::new(1, 0)
-        }
-    );
+    assert_eq!(deposit, common::deposit(1, 1, Decimal::new(1, 0)));
 
     let withdrawal = reader.next().unwrap()?;
-    assert_eq!(
-        withdrawal,
-        Transaction::Withdrawal {
-            client: ClientId(1),
-            trade: TransactionId(4),
-            amount: Decimal::new(15, 1)
-        }
-    );
+    assert_eq!(withdrawal, common::withdrawal(1, 4, Decimal::new(15, 1)));
 
     let dispute = reader.next().unwrap()?;
-    assert_eq!(
-        dispute,
-        Transaction::Dispute {
-            client: ClientId(1),
-            trade: TransactionId(1),
-        }
-    );
+    assert_eq!(dispute, common::dispute(1, 1));
 
     let resolve = reader.next().unwrap()?;
     assert_eq!(
@@ -79,14 +63,97 @@ fn read_csv_files_with_whitespaces() -> anyhow::Result<()> {
     let mut reader = CsvReader::from_file(file.reopen()?)?;
 
     let deposit = reader.next().unwrap()?;
+    assert_eq!(deposit, common::deposit(1, 1, Decimal::new(1, 0)));
+
+    Ok(())
+}
+
+#[test]
+fn read_transfer_from_file() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(file, "type,client,tx,amount,to")?;
+    writeln!(file, "deposit,1,1,1.0,")?;
+    writeln!(file, "transfer,1,2,0.5,2")?;
+
+    let mut reader = CsvReader::from_file(file.reopen()?)?;
+
+    reader.next().unwrap()?;
+
+    let transfer = reader.next().unwrap()?;
     assert_eq!(
-        deposit,
-        Transaction::Deposit {
+        transfer,
+        Transaction::Transfer {
             client: ClientId(1),
-            trade: TransactionId(1),
-            amount: Decimal::new(1, 0)
+            to: ClientId(2),
+            trade: TransactionId(2),
+            amount: Decimal::new(5, 1),
+            asset: Asset::default(),
         }
     );
 
     Ok(())
 }
+
+#[test]
+fn read_origin_of_transactions() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(file, "type,client,tx,amount")?;
+    writeln!(file, "deposit, 1, 1, 1.0")?;
+    writeln!(file, "unknown,1,2,")?;
+
+    let mut reader = CsvReader::from_file(file.reopen()?)?;
+
+    assert!(reader.next().unwrap().is_ok());
+    assert_eq!(
+        reader.origin(),
+        Some(Origin {
+            line: 2,
+            row: String::from("deposit, 1, 1, 1.0")
+        })
+    );
+
+    assert!(reader.next().unwrap().is_err());
+    assert_eq!(
+        reader.origin(),
+        Some(Origin {
+            line: 3,
+            row: String::from("unknown,1,2,")
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn origin_keeps_quoted_fields() -> anyhow::Result<()> {
+    let content = "type,client,tx,amount,currency\ndeposit,1,1,abc,\"EUR, \"\"old\"\"\"\n";
+
+    let mut reader = CsvReader::from_reader(content.as_bytes())?;
+
+    assert!(reader.next().unwrap().is_err());
+    assert_eq!(
+        reader.origin().map(|origin| origin.row),
+        Some(String::from("deposit,1,1,abc,\"EUR, \"\"old\"\"\""))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn read_transactions_from_memory() -> anyhow::Result<()> {
+    let content = "type,client,tx,amount\ndeposit,1,1,1.0\nwithdrawal,1,2,0.5\n";
+
+    let mut reader = CsvReader::from_reader(content.as_bytes())?;
+
+    let deposit = reader.next().unwrap()?;
+    assert_eq!(deposit, common::deposit(1, 1, Decimal::new(1, 0)));
+
+    let withdrawal = reader.next().unwrap()?;
+    assert_eq!(withdrawal, common::withdrawal(1, 2, Decimal::new(5, 1)));
+
+    assert!(reader.next().is_none());
+
+    Ok(())
+}
@@ -0,0 +1,177 @@
+mod common;
+
+use common::{deposit_in, dispute};
+use indoc::indoc;
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::errors::EngineError;
+use payment_engine::input::csv::CsvReader;
+use payment_engine::input::reader::InputReader;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+#[test]
+fn read_optional_currency_column() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(file, "type,client,tx,amount,currency")?;
+    writeln!(file, "deposit,1,1,1.0,EUR")?;
+    writeln!(file, "deposit,1,2,2.0,")?;
+
+    let mut reader = CsvReader::from_file(file.reopen()?)?;
+
+    assert_eq!(
+        reader.next().unwrap()?,
+        deposit_in(1, 1, dec!(1.0), Asset::new("EUR"))
+    );
+    assert_eq!(
+        reader.next().unwrap()?,
+        deposit_in(1, 2, dec!(2.0), Asset::new(""))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn report_row_per_client_and_asset() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine
+        .process(deposit_in(1, 1, dec!(2), Asset::new("USD")))
+        .await?;
+    engine
+        .process(deposit_in(1, 2, dec!(1.5), Asset::new("EUR")))
+        .await?;
+    engine
+        .process(deposit_in(2, 3, dec!(3), Asset::new("USD")))
+        .await?;
+
+    let report = engine.report().await?;
+
+    assert_eq!(
+        report.to_string(),
+        indoc! {"
+            client,currency,available,held,total,locked
+            1,EUR,1.5000,0.0000,1.5000,false
+            1,USD,2.0000,0.0000,2.0000,false
+            2,USD,3.0000,0.0000,3.0000,false
+        "}
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn withdrawal_needs_funds_in_the_same_asset() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(1);
+
+    engine
+        .process(deposit_in(1, 1, dec!(5), Asset::new("EUR")))
+        .await?;
+
+    let ack = engine
+        .submit(Transaction::Withdrawal {
+            client: ClientId(1),
+            trade: TransactionId(2),
+            amount: dec!(1),
+            asset: Asset::new("USD"),
+            timestamp: None,
+        })
+        .await?;
+
+    assert_eq!(ack.await, Err(EngineError::NotEnoughMany(TransactionId(2))));
+
+    let account = engine.account_in(ClientId(1), Asset::new("EUR")).await?;
+
+    assert_eq!(account.map(|account| account.available), Some(dec!(5)));
+    assert_eq!(
+        engine.account_in(ClientId(1), Asset::new("USD")).await?,
+        None
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dispute_holds_funds_in_the_original_asset() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(1);
+
+    engine
+        .process(deposit_in(1, 1, dec!(2), Asset::new("EUR")))
+        .await?;
+    engine
+        .process(deposit_in(1, 2, dec!(3), Asset::new("USD")))
+        .await?;
+    engine.process(dispute(1, 1)).await?;
+
+    let eur = engine
+        .account_in(ClientId(1), Asset::new("EUR"))
+        .await?
+        .unwrap();
+    let usd = engine
+        .account_in(ClientId(1), Asset::new("USD"))
+        .await?
+        .unwrap();
+
+    assert_eq!(
+        (eur.available, eur.held, eur.total),
+        (dec!(0), dec!(2), dec!(2))
+    );
+    assert_eq!(
+        (usd.available, usd.held, usd.total),
+        (dec!(3), dec!(0), dec!(3))
+    );
+
+    engine
+        .process(Transaction::Chargeback {
+            client: ClientId(1),
+            trade: TransactionId(1),
+        })
+        .await?;
+
+    let report = engine.report().await?;
+    let accounts = report.accounts();
+
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].asset, Asset::new("EUR"));
+    assert_eq!(accounts[0].total, dec!(0));
+    assert_eq!(accounts[1].total, dec!(3));
+    assert!(accounts.iter().all(|account| account.locked));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transfer_moves_funds_in_its_asset() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine
+        .process(deposit_in(1, 1, dec!(4), Asset::new("BTC")))
+        .await?;
+    engine
+        .process(Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(2),
+            trade: TransactionId(2),
+            amount: dec!(1.5),
+            asset: Asset::new("BTC"),
+        })
+        .await?;
+
+    let report = engine.report().await?;
+    let accounts = report.accounts();
+
+    assert_eq!(accounts.len(), 2);
+    assert!(
+        accounts
+            .iter()
+            .all(|account| account.asset == Asset::new("BTC"))
+    );
+    assert_eq!(accounts[0].total, dec!(2.5));
+    assert_eq!(accounts[1].total, dec!(1.5));
+
+    Ok(())
+}
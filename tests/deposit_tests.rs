@@ -1,6 +1,6 @@
 use payment_engine::core::wallet::AccountWallet;
 use payment_engine::errors::EngineError;
-use payment_engine::model::account::Account;
+use payment_engine::model::asset::Asset;
 use payment_engine::model::client::ClientId;
 use payment_engine::model::trade::TransactionId;
 use rust_decimal_macros::dec;
@@ -12,7 +12,7 @@ fn deposit_negative_amount() -> anyhow::Result<()> {
     let trade = TransactionId(1);
     let amount = dec!(-1.5);
 
-    let confirmation = wallet.deposit(trade, amount);
+    let confirmation = wallet.deposit(trade, Asset::default(), amount);
 
     assert!(confirmation.is_err());
     assert_eq!(confirmation, Err(EngineError::NegativeAmount(trade)));
@@ -27,7 +27,7 @@ fn deposit_zero_amount() -> anyhow::Result<()> {
     let trade = TransactionId(1);
     let amount = dec!(0.0);
 
-    let confirmation = wallet.deposit(trade, amount);
+    let confirmation = wallet.deposit(trade, Asset::default(), amount);
 
     assert!(confirmation.is_err());
     assert_eq!(confirmation, Err(EngineError::NegativeAmount(trade)));
@@ -42,18 +42,18 @@ fn deposit_positive_amount() -> anyhow::Result<()> {
     let trade = TransactionId(1);
     let amount = dec!(1.5);
 
-    let confirmation = wallet.deposit(trade, amount);
+    let confirmation = wallet.deposit(trade, Asset::default(), amount);
 
     assert!(confirmation.is_ok());
 
     let trade = TransactionId(2);
     let amount = dec!(2.0);
 
-    let confirmation = wallet.deposit(trade, amount);
+    let confirmation = wallet.deposit(trade, Asset::default(), amount);
 
     assert!(confirmation.is_ok());
 
-    let account: Account = wallet.into();
+    let account = wallet.account(&Asset::default()).unwrap();
 
     assert_eq!(account.client, ClientId(1));
     assert_eq!(account.available, dec!(3.5));
@@ -1,6 +1,7 @@
-use payment_engine::core::wallet::AccountWallet;
+use payment_engine::core::policy::WalletPolicy;
+use payment_engine::core::wallet::{AccountWallet, TradeState};
 use payment_engine::errors::EngineError;
-use payment_engine::model::account::Account;
+use payment_engine::model::asset::Asset;
 use payment_engine::model::client::ClientId;
 use payment_engine::model::trade::TransactionId;
 use rust_decimal_macros::dec;
@@ -10,11 +11,11 @@ fn init_wallet(client_id: ClientId) -> anyhow::Result<AccountWallet> {
 
     let trade = TransactionId(1);
     let amount = dec!(2);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     let trade = TransactionId(2);
     let amount = dec!(5);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     Ok(wallet)
 }
@@ -40,7 +41,7 @@ fn dispute_more_than_account_has() -> anyhow::Result<()> {
     let trade = TransactionId(3);
     let amount = dec!(6);
 
-    wallet.withdrawal(trade, amount)?;
+    wallet.withdrawal(trade, Asset::default(), amount)?;
 
     let trade = TransactionId(2);
 
@@ -62,7 +63,7 @@ fn dispute_positive_amount() -> anyhow::Result<()> {
 
     assert!(confirmation.is_ok());
 
-    let account: Account = wallet.into();
+    let account = wallet.account(&Asset::default()).unwrap();
 
     assert_eq!(account.client, ClientId(1));
     assert_eq!(account.available, dec!(2));
@@ -72,3 +73,130 @@ fn dispute_positive_amount() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn dispute_withdrawal() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(3);
+    let amount = dec!(6);
+
+    wallet.withdrawal(trade, Asset::default(), amount)?;
+
+    let confirmation = wallet.dispute(trade);
+
+    assert!(confirmation.is_ok());
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert_eq!(account.client, ClientId(1));
+    assert_eq!(account.available, dec!(1));
+    assert_eq!(account.held, dec!(6));
+    assert_eq!(account.total, dec!(7));
+    assert!(!account.locked);
+
+    Ok(())
+}
+
+#[test]
+fn dispute_withdrawal_of_locked_account() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(3);
+    let amount = dec!(1);
+
+    wallet.withdrawal(trade, Asset::default(), amount)?;
+
+    wallet.dispute(TransactionId(2))?;
+    wallet.chargeback(TransactionId(2))?;
+
+    let confirmation = wallet.dispute(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::FrozenAccount(ClientId(1))));
+
+    Ok(())
+}
+
+#[test]
+fn dispute_already_disputed() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(2);
+
+    wallet.dispute(trade)?;
+
+    let confirmation = wallet.dispute(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::AlreadyDisputed(trade)));
+    assert_eq!(wallet.state(trade), Some(TradeState::Disputed));
+
+    Ok(())
+}
+
+#[test]
+fn dispute_charged_back() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(2);
+
+    wallet.dispute(trade)?;
+    wallet.chargeback(trade)?;
+
+    let confirmation = wallet.dispute(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::AlreadyChargedBack(trade)));
+    assert_eq!(wallet.state(trade), Some(TradeState::ChargedBack));
+
+    Ok(())
+}
+
+#[test]
+fn dispute_resolved_without_redispute_policy() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(2);
+
+    wallet.dispute(trade)?;
+    wallet.resolve(trade)?;
+
+    let confirmation = wallet.dispute(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::AlreadyResolved(trade)));
+    assert_eq!(wallet.state(trade), Some(TradeState::Resolved));
+
+    Ok(())
+}
+
+#[test]
+fn dispute_resolved_with_redispute_policy() -> anyhow::Result<()> {
+    let policy = WalletPolicy {
+        allow_redispute: true,
+        ..WalletPolicy::default()
+    };
+
+    let mut wallet = AccountWallet::with_policy(ClientId(1), policy);
+
+    let trade = TransactionId(1);
+    let amount = dec!(2);
+
+    wallet.deposit(trade, Asset::default(), amount)?;
+    wallet.dispute(trade)?;
+    wallet.resolve(trade)?;
+
+    let confirmation = wallet.dispute(trade);
+
+    assert!(confirmation.is_ok());
+    assert_eq!(wallet.state(trade), Some(TradeState::Disputed));
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert_eq!(account.available, dec!(0));
+    assert_eq!(account.held, dec!(2));
+    assert_eq!(account.total, dec!(2));
+
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use indoc::indoc;
+use payment_engine::core::builder::EngineConfig;
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::policy::{DisputeWindow, MAX_CLOCK_SKEW, WalletPolicy};
+use payment_engine::core::wallet::{AccountWallet, TradeState};
+use payment_engine::errors::EngineError;
+use payment_engine::input::csv::CsvReader;
+use payment_engine::input::reader::InputReader;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Timestamp, Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tempfile::NamedTempFile;
+
+const DAY: u64 = 24 * 60 * 60;
+
+fn wallet(window: DisputeWindow) -> AccountWallet {
+    let policy = WalletPolicy {
+        dispute_window: Some(window),
+        ..WalletPolicy::default()
+    };
+
+    AccountWallet::with_policy(ClientId(1), policy)
+}
+
+#[test]
+fn dispute_after_window_is_rejected() -> anyhow::Result<()> {
+    let mut wallet = wallet(DisputeWindow::Seconds(120 * DAY));
+
+    let asset = Asset::default();
+
+    wallet.deposit_with_fee(
+        TransactionId(1),
+        asset.clone(),
+        dec!(2),
+        dec!(0),
+        Some(Timestamp(0)),
+    )?;
+    wallet.advance(Timestamp(0));
+    wallet.deposit_with_fee(
+        TransactionId(2),
+        asset,
+        dec!(3),
+        dec!(0),
+        Some(Timestamp(100 * DAY)),
+    )?;
+    wallet.advance(Timestamp(100 * DAY));
+
+    assert_eq!(
+        wallet.dispute_at(TransactionId(1), Some(Timestamp(121 * DAY))),
+        Err(EngineError::DisputeWindowExpired(TransactionId(1)))
+    );
+    assert_eq!(wallet.state(TransactionId(1)), Some(TradeState::Processed));
+
+    wallet.dispute_at(TransactionId(2), Some(Timestamp(121 * DAY)))?;
+    wallet.advance(Timestamp(121 * DAY));
+
+    assert_eq!(wallet.state(TransactionId(1)), None);
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert_eq!(account.available, dec!(2));
+    assert_eq!(account.held, dec!(3));
+
+    Ok(())
+}
+
+#[test]
+fn window_counts_newer_transactions() -> anyhow::Result<()> {
+    let mut wallet = wallet(DisputeWindow::Transactions(2));
+
+    for trade in 1..=3 {
+        wallet.deposit(TransactionId(trade), Asset::default(), dec!(1))?;
+    }
+
+    assert_eq!(wallet.state(TransactionId(1)), None);
+    assert_eq!(
+        wallet.dispute(TransactionId(1)),
+        Err(EngineError::DisputeWindowExpired(TransactionId(1)))
+    );
+    assert_eq!(wallet.dispute(TransactionId(2)), Ok(()));
+
+    Ok(())
+}
+
+#[test]
+fn disputed_transaction_is_evicted_once_resolved() -> anyhow::Result<()> {
+    let mut wallet = wallet(DisputeWindow::Transactions(1));
+
+    wallet.deposit(TransactionId(1), Asset::default(), dec!(1))?;
+    wallet.dispute(TransactionId(1))?;
+    wallet.deposit(TransactionId(2), Asset::default(), dec!(1))?;
+
+    assert_eq!(wallet.state(TransactionId(1)), Some(TradeState::Disputed));
+
+    wallet.resolve(TransactionId(1))?;
+
+    assert_eq!(wallet.state(TransactionId(1)), None);
+    assert_eq!(
+        wallet.dispute(TransactionId(1)),
+        Err(EngineError::DisputeWindowExpired(TransactionId(1)))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn transactions_without_timestamp_never_age_out() -> anyhow::Result<()> {
+    let mut wallet = wallet(DisputeWindow::Seconds(DAY));
+
+    wallet.deposit(TransactionId(1), Asset::default(), dec!(1))?;
+    wallet.deposit_with_fee(
+        TransactionId(2),
+        Asset::default(),
+        dec!(1),
+        dec!(0),
+        Some(Timestamp(0)),
+    )?;
+    wallet.advance(Timestamp(2 * DAY));
+
+    assert_eq!(wallet.state(TransactionId(2)), None);
+    assert_eq!(wallet.dispute(TransactionId(1)), Ok(()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn rejected_transaction_does_not_move_the_clock() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::builder()
+        .workers(1)
+        .dispute_window(DisputeWindow::Seconds(DAY))
+        .build()?;
+
+    let transactions = [
+        Transaction::Deposit {
+            client: ClientId(1),
+            trade: TransactionId(1),
+            amount: dec!(1),
+            asset: Asset::default(),
+            timestamp: Some(Timestamp(0)),
+        },
+        Transaction::Withdrawal {
+            client: ClientId(1),
+            trade: TransactionId(2),
+            amount: dec!(5),
+            asset: Asset::default(),
+            timestamp: Some(Timestamp(10 * DAY)),
+        },
+        Transaction::Dispute {
+            client: ClientId(1),
+            trade: TransactionId(1),
+            timestamp: Some(Timestamp(DAY / 2)),
+        },
+    ];
+
+    let mut results = vec![];
+
+    for tx in transactions {
+        results.push(engine.submit(tx).await?.await);
+    }
+
+    assert_eq!(
+        results[1],
+        Err(EngineError::NotEnoughMany(TransactionId(2)))
+    );
+    assert_eq!(results[2], Ok(()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_timestamp_far_in_the_future() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::builder()
+        .workers(1)
+        .dispute_window(DisputeWindow::Seconds(DAY))
+        .build()?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+    let result = engine
+        .submit(Transaction::Deposit {
+            client: ClientId(1),
+            trade: TransactionId(1),
+            amount: dec!(1),
+            asset: Asset::default(),
+            timestamp: Some(Timestamp(now + DAY)),
+        })
+        .await?
+        .await;
+
+    assert_eq!(result, Err(EngineError::FutureTimestamp(TransactionId(1))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn timestamps_are_read_from_csv() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(file, "type,client,tx,amount,timestamp")?;
+    writeln!(file, "deposit,1,1,2.0,1700000000")?;
+    writeln!(file, "deposit,1,2,1.0,")?;
+    writeln!(file, "dispute,1,1,,1720000000")?;
+
+    let mut engine = PaymentEngine::builder()
+        .workers(1)
+        .dispute_window(DisputeWindow::Seconds(120 * DAY))
+        .build()?;
+
+    let mut reader = CsvReader::from_file(file.reopen()?)?;
+    let mut transactions = vec![];
+
+    while let Some(tx) = reader.next() {
+        transactions.push(tx?);
+    }
+
+    assert_eq!(transactions[0].timestamp(), Some(Timestamp(1700000000)));
+    assert_eq!(transactions[1].timestamp(), None);
+
+    let mut results = vec![];
+
+    for tx in transactions {
+        results.push(engine.submit(tx).await?.await);
+    }
+
+    assert_eq!(
+        results[2],
+        Err(EngineError::DisputeWindowExpired(TransactionId(1)))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn load_dispute_window_from_config() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    file.write_all(
+        indoc! {r#"
+            dispute_window = { seconds = 10368000 }
+        "#}
+        .as_bytes(),
+    )?;
+
+    let config = EngineConfig::load(file.path().to_str().unwrap())?;
+
+    assert_eq!(
+        config.dispute_window,
+        Some(DisputeWindow::Seconds(10368000))
+    );
+
+    let result = PaymentEngine::builder()
+        .dispute_window(DisputeWindow::Transactions(0))
+        .build();
+
+    assert!(matches!(result.err(), Some(EngineError::InvalidConfig(_))));
+
+    Ok(())
+}
+
+#[test]
+fn check_timestamp_against_given_clock() {
+    let policy = WalletPolicy::default();
+    let now = Timestamp(DAY);
+
+    assert_eq!(
+        policy.check_timestamp(TransactionId(1), Timestamp(DAY + MAX_CLOCK_SKEW), now),
+        Ok(())
+    );
+    assert_eq!(
+        policy.check_timestamp(TransactionId(2), Timestamp(DAY + MAX_CLOCK_SKEW + 1), now),
+        Err(EngineError::FutureTimestamp(TransactionId(2)))
+    );
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::{deposit, dispute, withdrawal};
+use indoc::indoc;
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::errors::EngineError;
+use payment_engine::model::trade::TransactionId;
+use rust_decimal_macros::dec;
+
+#[tokio::test]
+async fn duplicated_deposit_for_same_client() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::default();
+
+    let trade = TransactionId(1);
+
+    engine.process(deposit(1, trade.0, dec!(2))).await?;
+
+    let confirmation = engine.process(deposit(1, trade.0, dec!(5))).await;
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::DuplicateTransaction(trade)));
+
+    let expected = indoc! {r#"
+        client,available,held,total,locked
+        1,2.0000,0.0000,2.0000,false
+    "#};
+
+    assert_eq!(engine.report().await?.to_string(), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn duplicated_transaction_across_clients() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    let trade = TransactionId(1);
+
+    engine.process(deposit(1, trade.0, dec!(2))).await?;
+
+    let confirmation = engine.process(deposit(2, trade.0, dec!(5))).await;
+
+    assert_eq!(confirmation, Err(EngineError::DuplicateTransaction(trade)));
+
+    let confirmation = engine.process(withdrawal(3, trade.0, dec!(1))).await;
+
+    assert_eq!(confirmation, Err(EngineError::DuplicateTransaction(trade)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn dispute_refers_to_processed_transaction() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::default();
+
+    let trade = TransactionId(1);
+
+    engine.process(deposit(1, trade.0, dec!(2))).await?;
+
+    let confirmation = engine.process(dispute(1, trade.0)).await;
+
+    assert!(confirmation.is_ok());
+
+    let expected = indoc! {r#"
+        client,available,held,total,locked
+        1,0.0000,2.0000,2.0000,false
+    "#};
+
+    assert_eq!(engine.report().await?.to_string(), expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_amount_does_not_use_the_id() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(1);
+
+    let deposit = |amount| deposit(1, 1, amount);
+
+    assert_eq!(
+        engine.submit(deposit(dec!(1.12345))).await?.await,
+        Err(EngineError::InvalidPrecision(TransactionId(1)))
+    );
+    assert_eq!(engine.submit(deposit(dec!(1.1234))).await?.await, Ok(()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn id_rejected_by_wallet_stays_used() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(1);
+
+    let withdrawal = || withdrawal(1, 1, dec!(1));
+
+    assert_eq!(
+        engine.submit(withdrawal()).await?.await,
+        Err(EngineError::NotEnoughMany(TransactionId(1)))
+    );
+    assert_eq!(
+        engine.submit(withdrawal()).await?.await,
+        Err(EngineError::DuplicateTransaction(TransactionId(1)))
+    );
+
+    Ok(())
+}
@@ -7,7 +7,7 @@ use payment_engine::input::reader::InputReader;
 async fn end_to_end_test_based_on_csv_file() -> anyhow::Result<()> {
     let file = "transactions.csv";
 
-    let mut reader = CsvReader::new(&file)?;
+    let mut reader = CsvReader::new(file)?;
     let mut engine = PaymentEngine::default();
 
     while let Some(result) = reader.next() {
@@ -25,8 +25,8 @@ async fn end_to_end_test_based_on_csv_file() -> anyhow::Result<()> {
 
     let expected = indoc! {r#"
         client,available,held,total,locked
-        2,2,0,2,false
-        1,0.5,0,0.5,true
+        1,0.5000,1.5000,2.0000,true
+        2,2.0000,0.0000,2.0000,false
     "#};
 
     assert_eq!(report.to_string(), expected);
@@ -0,0 +1,135 @@
+use payment_engine::core::snapshot::EngineSnapshot;
+use payment_engine::errors::{EngineError, ErrorCategory};
+use payment_engine::input::csv::CsvReader;
+use payment_engine::input::reader::InputReader;
+use payment_engine::model::trade::TransactionId;
+use std::error::Error;
+use std::io::{self, ErrorKind, Read, Write};
+use tempfile::{NamedTempFile, TempDir};
+
+#[test]
+fn missing_file_is_reported_as_not_found() {
+    let error = CsvReader::new("missing.csv").err().unwrap();
+
+    assert!(matches!(error, EngineError::FileNotFound(_)));
+    assert_eq!(error.code(), 3001);
+    assert_eq!(error.category(), ErrorCategory::Storage);
+}
+
+#[test]
+fn other_io_errors_keep_their_kind() -> anyhow::Result<()> {
+    let error = EngineError::from(io::Error::new(ErrorKind::PermissionDenied, "denied"));
+
+    assert!(matches!(error, EngineError::Io(_)));
+    assert_eq!(error.code(), 3006);
+    assert_eq!(error.to_string(), "Io error: denied");
+
+    let directory = TempDir::new()?;
+    let error = CsvReader::new(directory.path().to_str().unwrap())
+        .err()
+        .unwrap();
+
+    assert!(matches!(error, EngineError::CsvIo { .. }));
+    assert_eq!(error.code(), 3008);
+    assert_eq!(error.category(), ErrorCategory::Storage);
+
+    Ok(())
+}
+
+#[test]
+fn csv_error_keeps_position_and_source() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(file, "type, client, tx, amount")?;
+    writeln!(file, "deposit, 1, 1, 1.0")?;
+    writeln!(file, "deposit, 1")?;
+
+    let mut reader = CsvReader::from_file(file.reopen()?)?;
+
+    assert!(reader.next().unwrap().is_ok());
+
+    let error = reader.next().unwrap().err().unwrap();
+
+    match &error {
+        EngineError::Csv { position, source } => {
+            assert_eq!(position.as_ref().map(|position| position.line()), Some(3));
+            assert!(source.get().downcast_ref::<csv::Error>().is_some());
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    assert!(error.to_string().starts_with("Csv error at line 3: "));
+    assert_eq!(error.code(), 2005);
+    assert_eq!(error.category(), ErrorCategory::Input);
+    assert!(
+        error
+            .source()
+            .and_then(Error::source)
+            .is_some_and(|source| source.is::<csv::Error>())
+    );
+
+    Ok(())
+}
+
+/// Returns the rows, then fails like a dropped connection.
+struct BrokenSource(io::Cursor<&'static str>);
+
+impl Read for BrokenSource {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buffer)? {
+            0 => Err(io::Error::new(ErrorKind::ConnectionReset, "reset")),
+            read => Ok(read),
+        }
+    }
+}
+
+#[test]
+fn csv_io_error_keeps_position() -> anyhow::Result<()> {
+    let source = BrokenSource(io::Cursor::new("type,client,tx,amount\ndeposit,1,1,1.0\n"));
+    let mut reader = CsvReader::from_reader(source)?;
+
+    assert!(reader.next().unwrap().is_ok());
+
+    let error = reader.next().unwrap().err().unwrap();
+
+    match &error {
+        EngineError::CsvIo { position, source } => {
+            assert_eq!(position.as_ref().map(|position| position.line()), Some(3));
+            assert!(source.get().downcast_ref::<csv::Error>().is_some());
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+
+    assert!(error.to_string().starts_with("Csv io error at line 3: "));
+    assert_eq!(error.category(), ErrorCategory::Storage);
+
+    Ok(())
+}
+
+#[test]
+fn business_errors_have_no_source() {
+    let error = EngineError::NotEnoughMany(TransactionId(1));
+
+    assert_eq!(error.category(), ErrorCategory::Business);
+    assert!(error.source().is_none());
+}
+
+#[test]
+fn json_errors_are_not_reported_as_snapshot() -> anyhow::Result<()> {
+    let error = EngineError::from(serde_json::from_str::<u32>("{").err().unwrap());
+
+    assert!(matches!(error, EngineError::Serialization(_)));
+    assert_eq!(error.code(), 3007);
+
+    let mut file = NamedTempFile::new()?;
+    writeln!(file, "{{")?;
+
+    let error = EngineSnapshot::load(file.path().to_str().unwrap())
+        .err()
+        .unwrap();
+
+    assert!(matches!(error, EngineError::Snapshot(_)));
+    assert_eq!(error.code(), 3003);
+
+    Ok(())
+}
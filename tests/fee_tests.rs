@@ -0,0 +1,259 @@
+mod common;
+
+use common::{deposit, dispute, withdrawal};
+use indoc::indoc;
+use payment_engine::core::builder::EngineConfig;
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::fees::{FeeRule, FeeSchedule, FeeTier};
+use payment_engine::errors::EngineError;
+use payment_engine::model::admin::{AdminAction, AdminCommand};
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use std::collections::BTreeMap;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+const HOUSE: ClientId = ClientId(0);
+
+fn engine(fees: FeeSchedule) -> anyhow::Result<PaymentEngine> {
+    Ok(PaymentEngine::builder()
+        .workers(2)
+        .house_account(HOUSE)
+        .fees(fees)
+        .build()?)
+}
+
+#[tokio::test]
+async fn withdrawal_fee_is_credited_to_house_account() -> anyhow::Result<()> {
+    let mut engine = engine(FeeSchedule {
+        withdrawal: Some(FeeRule::Flat(dec!(0.5))),
+        ..FeeSchedule::default()
+    })?;
+
+    engine.process(deposit(1, 1, dec!(10))).await?;
+    engine.process(withdrawal(1, 2, dec!(4))).await?;
+
+    let report = engine.report().await?;
+
+    assert_eq!(
+        report.to_string(),
+        indoc! {"
+            client,available,held,total,fees,locked
+            0,0.5000,0.0000,0.5000,0.0000,false
+            1,5.5000,0.0000,5.5000,0.5000,false
+        "}
+    );
+    assert_eq!(
+        report.fees(),
+        BTreeMap::from([(Asset::default(), dec!(0.5))])
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fees_are_credited_to_locked_house_account() -> anyhow::Result<()> {
+    let mut engine = engine(FeeSchedule {
+        withdrawal: Some(FeeRule::Flat(dec!(0.5))),
+        ..FeeSchedule::default()
+    })?;
+
+    engine
+        .admin(AdminCommand {
+            action: AdminAction::Lock,
+            client: HOUSE,
+            operator: String::from("ops"),
+            reason: String::from("audit"),
+        })
+        .await?;
+
+    engine.process(deposit(1, 1, dec!(10))).await?;
+    engine.process(withdrawal(1, 2, dec!(4))).await?;
+
+    let report = engine.report().await?;
+
+    assert_eq!(
+        report.to_string(),
+        indoc! {"
+            client,available,held,total,fees,locked,lock_reason
+            0,0.5000,0.0000,0.5000,0.0000,true,audit (by ops)
+            1,5.5000,0.0000,5.5000,0.5000,false,
+        "}
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn withdrawal_needs_funds_for_the_fee() -> anyhow::Result<()> {
+    let mut engine = engine(FeeSchedule {
+        withdrawal: Some(FeeRule::Percentage(dec!(1))),
+        ..FeeSchedule::default()
+    })?;
+
+    engine.process(deposit(1, 1, dec!(10))).await?;
+
+    assert_eq!(
+        engine.submit(withdrawal(1, 2, dec!(10))).await?.await,
+        Err(EngineError::NotEnoughMany(TransactionId(2)))
+    );
+    assert_eq!(
+        engine.submit(withdrawal(1, 3, dec!(9))).await?.await,
+        Ok(())
+    );
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.available, dec!(0.91));
+    assert_eq!(account.fees, dec!(0.09));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn chargeback_fee_is_charged_on_frozen_account() -> anyhow::Result<()> {
+    let mut engine = engine(FeeSchedule {
+        chargeback: Some(FeeRule::Percentage(dec!(10))),
+        ..FeeSchedule::default()
+    })?;
+
+    engine.process(deposit(1, 1, dec!(20))).await?;
+    engine.process(dispute(1, 1)).await?;
+    engine
+        .process(Transaction::Chargeback {
+            client: ClientId(1),
+            trade: TransactionId(1),
+        })
+        .await?;
+
+    let report = engine.snapshot().await?;
+    let accounts = report.accounts();
+
+    assert_eq!(accounts[0].client, HOUSE);
+    assert_eq!(accounts[0].total, dec!(2));
+    assert_eq!(accounts[1].total, dec!(-2));
+    assert_eq!(accounts[1].fees, dec!(2));
+    assert!(accounts[1].locked);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transfer_fee_is_paid_by_sender() -> anyhow::Result<()> {
+    let mut engine = engine(FeeSchedule {
+        transfer: Some(FeeRule::Flat(dec!(0.1))),
+        ..FeeSchedule::default()
+    })?;
+
+    engine.process(deposit(1, 1, dec!(5))).await?;
+    engine
+        .process(Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(2),
+            trade: TransactionId(2),
+            amount: dec!(2),
+            asset: Asset::default(),
+        })
+        .await?;
+
+    let report = engine.report().await?;
+    let totals: Vec<_> = report
+        .accounts()
+        .iter()
+        .map(|account| (account.client, account.total))
+        .collect();
+
+    assert_eq!(
+        totals,
+        vec![
+            (HOUSE, dec!(0.1)),
+            (ClientId(1), dec!(2.9)),
+            (ClientId(2), dec!(2))
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn tiered_deposit_fee() -> anyhow::Result<()> {
+    let mut engine = engine(FeeSchedule {
+        deposit: Some(FeeRule::Tiered(vec![
+            FeeTier {
+                from: dec!(0),
+                fee: FeeRule::Flat(dec!(1)),
+            },
+            FeeTier {
+                from: dec!(1000),
+                fee: FeeRule::Percentage(dec!(0.5)),
+            },
+        ])),
+        ..FeeSchedule::default()
+    })?;
+
+    assert_eq!(
+        engine.submit(deposit(1, 1, dec!(0.5))).await?.await,
+        Err(EngineError::NotEnoughMany(TransactionId(1)))
+    );
+
+    engine.process(deposit(1, 2, dec!(10))).await?;
+    engine.process(deposit(1, 3, dec!(2000))).await?;
+
+    let report = engine.report().await?;
+
+    assert_eq!(report.fees()[&Asset::default()], dec!(11));
+
+    Ok(())
+}
+
+#[test]
+fn load_fees_from_config() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    file.write_all(
+        indoc! {r#"
+            house_account = 0
+
+            [fees]
+            withdrawal = { flat = "0.5" }
+            chargeback = { percentage = "2" }
+            deposit = { tiered = [{ from = "0", fee = { flat = "0" } }, { from = "100", fee = { flat = "1" } }] }
+        "#}
+        .as_bytes(),
+    )?;
+
+    let config = EngineConfig::load(file.path().to_str().unwrap())?;
+
+    assert_eq!(config.house_account, Some(HOUSE));
+    assert_eq!(config.fees.withdrawal, Some(FeeRule::Flat(dec!(0.5))));
+    assert_eq!(config.fees.chargeback, Some(FeeRule::Percentage(dec!(2))));
+
+    Ok(())
+}
+
+#[test]
+fn fees_need_house_account() {
+    let fees = FeeSchedule {
+        withdrawal: Some(FeeRule::Flat(dec!(1))),
+        ..FeeSchedule::default()
+    };
+
+    let invalid = [
+        PaymentEngine::builder().fees(fees),
+        PaymentEngine::builder()
+            .house_account(HOUSE)
+            .fees(FeeSchedule {
+                deposit: Some(FeeRule::Flat(dec!(-1))),
+                ..FeeSchedule::default()
+            }),
+    ];
+
+    for builder in invalid {
+        assert!(matches!(
+            builder.build().err(),
+            Some(EngineError::InvalidConfig(_))
+        ));
+    }
+}
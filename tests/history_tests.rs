@@ -0,0 +1,170 @@
+mod common;
+
+use common::{deposit, dispute};
+use indoc::indoc;
+use payment_engine::core::builder::EngineConfig;
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::history::{HistoryConfig, TradeStore};
+use payment_engine::core::snapshot::EngineSnapshot;
+use payment_engine::core::supervisor::Supervision;
+use payment_engine::core::wallet::TradeState;
+use payment_engine::errors::{EngineError, EngineResult};
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tempfile::{NamedTempFile, TempDir};
+
+/// Store kept in memory, so the test can see what has been paged out.
+#[derive(Default)]
+struct MapStore {
+    values: Mutex<HashMap<(ClientId, TransactionId), Vec<u8>>>,
+}
+
+impl MapStore {
+    fn paged_out(&self) -> usize {
+        self.values.lock().unwrap().len()
+    }
+}
+
+impl TradeStore for MapStore {
+    fn put(&self, client: ClientId, id: TransactionId, value: &[u8]) -> EngineResult<()> {
+        self.values
+            .lock()
+            .unwrap()
+            .insert((client, id), value.to_vec());
+        Ok(())
+    }
+
+    fn get(&self, client: ClientId, id: TransactionId) -> EngineResult<Option<Vec<u8>>> {
+        Ok(self.values.lock().unwrap().get(&(client, id)).cloned())
+    }
+
+    fn remove(&self, client: ClientId, id: TransactionId) -> EngineResult<()> {
+        self.values.lock().unwrap().remove(&(client, id));
+        Ok(())
+    }
+
+    fn scan(&self, client: ClientId) -> EngineResult<Vec<(TransactionId, Vec<u8>)>> {
+        Ok(self
+            .values
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|((owner, _), _)| *owner == client)
+            .map(|((_, id), value)| (*id, value.clone()))
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn paged_out_deposit_can_be_disputed() -> anyhow::Result<()> {
+    let store = Arc::new(MapStore::default());
+    let mut engine = PaymentEngine::new(2).with_history(store.clone(), 2);
+
+    for trade in 1..=5 {
+        engine.process(deposit(1, trade, dec!(1))).await?;
+    }
+
+    engine.snapshot().await?;
+
+    assert_eq!(store.paged_out(), 3);
+
+    engine.process(dispute(1, 1)).await?;
+    engine
+        .process(Transaction::Chargeback {
+            client: ClientId(1),
+            trade: TransactionId(1),
+        })
+        .await?;
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.total, dec!(4));
+    assert!(account.locked);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_includes_paged_out_deposits() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let history = directory.path().join("history.db");
+    let state = directory.path().join("state.json");
+    let state = state.to_str().unwrap();
+
+    let mut engine = PaymentEngine::builder()
+        .workers(1)
+        .history(HistoryConfig::Disk {
+            path: history.to_str().unwrap().to_string(),
+        })
+        .build()?;
+
+    engine.process(deposit(1, 1, dec!(2))).await?;
+    engine.process(deposit(1, 2, dec!(3))).await?;
+    engine.process(dispute(1, 2)).await?;
+    engine.checkpoint(state).await?;
+
+    let snapshot = EngineSnapshot::load(state)?;
+    let wallet = &snapshot.wallets[0];
+
+    assert_eq!(wallet.state(TransactionId(1)), Some(TradeState::Processed));
+    assert_eq!(wallet.state(TransactionId(2)), Some(TradeState::Disputed));
+
+    let mut engine = PaymentEngine::new(1).restore(snapshot)?;
+
+    engine.process(dispute(1, 1)).await?;
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.held, dec!(5));
+    assert_eq!(account.available, dec!(0));
+
+    Ok(())
+}
+
+#[test]
+fn load_history_from_config() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    file.write_all(
+        indoc! {r#"
+            [history]
+            store = "spill"
+            path = "history.db"
+            capacity = 1000
+        "#}
+        .as_bytes(),
+    )?;
+
+    let config = EngineConfig::load(file.path().to_str().unwrap())?;
+
+    assert_eq!(
+        config.history,
+        HistoryConfig::Spill {
+            path: "history.db".to_string(),
+            capacity: 1000
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn history_store_cannot_restart_workers() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let history = directory.path().join("history.db");
+
+    let result = PaymentEngine::builder()
+        .supervision(Supervision::Restart)
+        .history(HistoryConfig::Disk {
+            path: history.to_str().unwrap().to_string(),
+        })
+        .build();
+
+    assert!(matches!(result.err(), Some(EngineError::InvalidConfig(_))));
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{deposit, dispute, withdrawal};
+use payment_engine::errors::EngineError;
+use payment_engine::input::json::JsonLinesReader;
+use payment_engine::input::reader::InputReader;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::rejection::Origin;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+#[test]
+fn read_transactions_from_file() -> anyhow::Result<()> {
+    let mut file = NamedTempFile::new()?;
+
+    writeln!(
+        file,
+        r#"{{"type":"deposit","client":1,"tx":1,"amount":1.0001}}"#
+    )?;
+    writeln!(
+        file,
+        r#"{{"type":"withdrawal","client":1,"tx":2,"amount":"0.5"}}"#
+    )?;
+    writeln!(file, r#"{{"type":"dispute","client":1,"tx":1}}"#)?;
+    writeln!(
+        file,
+        r#"{{"type":"transfer","client":1,"tx":3,"amount":0.25,"to":2}}"#
+    )?;
+
+    let mut reader = JsonLinesReader::from_file(file.reopen()?)?;
+
+    assert_eq!(reader.next().unwrap()?, deposit(1, 1, dec!(1.0001)));
+
+    assert_eq!(reader.next().unwrap()?, withdrawal(1, 2, dec!(0.5)));
+
+    assert_eq!(reader.next().unwrap()?, dispute(1, 1));
+
+    assert_eq!(
+        reader.next().unwrap()?,
+        Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(2),
+            trade: TransactionId(3),
+            amount: dec!(0.25),
+            asset: Asset::default(),
+        }
+    );
+
+    assert!(reader.next().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn number_amount_keeps_exact_scale() -> anyhow::Result<()> {
+    let input = r#"{"type":"deposit","client":1,"tx":1,"amount":0.12345}"#;
+
+    let mut reader = JsonLinesReader::from_reader(input.as_bytes())?;
+
+    let transaction = reader.next().unwrap()?;
+    assert_eq!(transaction, deposit(1, 1, dec!(0.12345)));
+
+    Ok(())
+}
+
+#[test]
+fn skip_blank_lines_and_track_origin() -> anyhow::Result<()> {
+    let input = "\n{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":1}\n\n{\"type\":\"withdrawal\",\"client\":1,\"tx\":2}\n";
+
+    let mut reader = JsonLinesReader::from_reader(input.as_bytes())?;
+
+    assert!(reader.next().unwrap().is_ok());
+    assert_eq!(
+        reader.origin(),
+        Some(Origin {
+            line: 2,
+            row: r#"{"type":"deposit","client":1,"tx":1,"amount":1}"#.to_string()
+        })
+    );
+
+    assert_eq!(reader.next().unwrap(), Err(EngineError::MissingAmount()));
+    assert_eq!(reader.origin().map(|origin| origin.line), Some(4));
+
+    assert!(reader.next().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn invalid_json_is_rejected() -> anyhow::Result<()> {
+    let input =
+        "{\"type\":\"deposit\",\"client\":1\n{\"type\":\"unknown\",\"client\":1,\"tx\":1}\n";
+
+    let mut reader = JsonLinesReader::from_reader(input.as_bytes())?;
+
+    assert!(matches!(
+        reader.next(),
+        Some(Err(EngineError::Json { line: Some(_), .. }))
+    ));
+    assert!(matches!(
+        reader.next(),
+        Some(Err(EngineError::Json { line: Some(_), .. }))
+    ));
+    assert!(reader.next().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn io_error_moves_origin_to_its_line() -> anyhow::Result<()> {
+    let input: &[u8] = b"{\"type\":\"deposit\",\"client\":1,\"tx\":1,\"amount\":1}\n\xff\n";
+
+    let mut reader = JsonLinesReader::from_reader(input)?;
+
+    assert!(reader.next().unwrap().is_ok());
+    assert!(matches!(reader.next(), Some(Err(EngineError::Io(_)))));
+    assert_eq!(
+        reader.origin(),
+        Some(Origin {
+            line: 2,
+            row: String::new()
+        })
+    );
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use common::{deposit, dispute, withdrawal};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::fees::{FeeRule, FeeSchedule};
+use payment_engine::core::snapshot::EngineSnapshot;
+use payment_engine::core::wallet::AccountWallet;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::ledger::{EntryKind, JournalEntry, LedgerAccount};
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+fn entry(
+    kind: EntryKind,
+    debit: LedgerAccount,
+    credit: LedgerAccount,
+    amount: Decimal,
+) -> JournalEntry {
+    JournalEntry {
+        client: ClientId(1),
+        tx: TransactionId(1),
+        kind,
+        asset: Asset::default(),
+        debit,
+        credit,
+        amount,
+    }
+}
+
+#[test]
+fn dispute_and_chargeback_are_posted_as_balanced_entries() -> anyhow::Result<()> {
+    let mut wallet = AccountWallet::new(ClientId(1));
+
+    wallet.deposit(TransactionId(1), Asset::default(), dec!(3))?;
+    wallet.dispute(TransactionId(1))?;
+    wallet.chargeback(TransactionId(1))?;
+
+    assert_eq!(
+        wallet.take_entries(),
+        [
+            entry(
+                EntryKind::Deposit,
+                LedgerAccount::External,
+                LedgerAccount::Available,
+                dec!(3)
+            ),
+            entry(
+                EntryKind::Dispute,
+                LedgerAccount::Available,
+                LedgerAccount::Held,
+                dec!(3)
+            ),
+            entry(
+                EntryKind::Chargeback,
+                LedgerAccount::Held,
+                LedgerAccount::External,
+                dec!(3)
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn balances_match_the_journal() -> anyhow::Result<()> {
+    let engine = PaymentEngine::new(2).with_fees(
+        Arc::new(FeeSchedule {
+            transfer: Some(FeeRule::Flat(dec!(0.1))),
+            ..FeeSchedule::default()
+        }),
+        ClientId(0),
+    );
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut engine = engine.with_journal(sender);
+
+    engine.process(deposit(1, 1, dec!(10))).await?;
+    engine.process(withdrawal(1, 2, dec!(2))).await?;
+    engine.process(dispute(1, 2)).await?;
+    engine
+        .process(Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(2),
+            trade: TransactionId(3),
+            amount: dec!(4),
+            asset: Asset::default(),
+        })
+        .await?;
+
+    let report = engine.report().await?;
+    let mut totals: HashMap<ClientId, Decimal> = HashMap::new();
+
+    while let Some(entry) = receiver.recv().await {
+        *totals.entry(entry.client).or_default() -= client_side(entry.debit, entry.amount);
+        *totals.entry(entry.client).or_default() += client_side(entry.credit, entry.amount);
+    }
+
+    assert_eq!(report.accounts().len(), 3);
+
+    for account in report.accounts() {
+        assert_eq!(totals[&account.client], account.total);
+    }
+
+    assert_eq!(totals[&ClientId(1)], dec!(5.9));
+
+    Ok(())
+}
+
+/// Part of the amount which stays with the client.
+fn client_side(account: LedgerAccount, amount: Decimal) -> Decimal {
+    match account {
+        LedgerAccount::Available | LedgerAccount::Held | LedgerAccount::Reserved => amount,
+        LedgerAccount::Fees | LedgerAccount::External => Decimal::ZERO,
+    }
+}
+
+#[tokio::test]
+async fn balances_are_restored_from_snapshot() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("state.json");
+    let path = path.to_str().unwrap();
+
+    let mut engine = PaymentEngine::new(1);
+
+    engine.process(deposit(1, 1, dec!(2))).await?;
+    engine.process(deposit(1, 2, dec!(1.5))).await?;
+    engine.process(dispute(1, 2)).await?;
+    engine.checkpoint(path).await?;
+
+    let before = engine.report().await?;
+
+    let engine = PaymentEngine::new(3).restore(EngineSnapshot::load(path)?)?;
+
+    assert_eq!(engine.report().await?.to_string(), before.to_string());
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use common::{deposit, dispute};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::model::account::{Account, LockReason};
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use tokio::sync::Mutex;
+
+#[tokio::test]
+async fn query_account_while_processing() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine.process(deposit(1, 1, dec!(2))).await?;
+    engine.process(dispute(1, 1)).await?;
+
+    assert_eq!(
+        engine.account(ClientId(1)).await?,
+        Some(Account {
+            client: ClientId(1),
+            asset: Asset::default(),
+            available: dec!(0),
+            held: dec!(2),
+            total: dec!(2),
+            fees: dec!(0),
+            locked: false,
+            lock_reason: None,
+        })
+    );
+
+    engine
+        .process(Transaction::Chargeback {
+            client: ClientId(1),
+            trade: TransactionId(1),
+        })
+        .await?;
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert!(account.locked);
+    assert_eq!(
+        account.lock_reason,
+        Some(LockReason::Chargeback(TransactionId(1)))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_unknown_account() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    assert_eq!(engine.account(ClientId(1)).await?, None);
+
+    engine.process(deposit(1, 1, dec!(2))).await?;
+
+    assert_eq!(engine.account(ClientId(3)).await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn snapshot_keeps_engine_running() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine.process(deposit(2, 1, dec!(1))).await?;
+    engine.process(deposit(1, 2, dec!(3))).await?;
+
+    let snapshot = engine.snapshot().await?;
+
+    assert_eq!(
+        snapshot.to_string(),
+        "client,available,held,total,locked\n1,3.0000,0.0000,3.0000,false\n2,1.0000,0.0000,1.0000,false\n"
+    );
+
+    engine.process(deposit(1, 3, dec!(1))).await?;
+
+    let report = engine.report().await?;
+
+    assert_eq!(report.accounts()[0].total, dec!(4));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_without_the_engine() -> anyhow::Result<()> {
+    let engine = Mutex::new(PaymentEngine::new(2));
+    let queries = engine.lock().await.queries();
+
+    let mut ingestion = engine.lock().await;
+
+    ingestion.process(deposit(1, 1, dec!(2))).await?;
+    ingestion.process(deposit(2, 2, dec!(3))).await?;
+
+    assert_eq!(
+        queries
+            .account_in(ClientId(1), Asset::default())
+            .await?
+            .map(|account| account.total),
+        Some(dec!(2))
+    );
+    assert_eq!(
+        queries.report().await?.to_string(),
+        "client,available,held,total,locked\n1,2.0000,0.0000,2.0000,false\n2,3.0000,0.0000,3.0000,false\n"
+    );
+
+    drop(ingestion);
+
+    let report = engine.into_inner().report().await?;
+
+    assert_eq!(report.accounts().len(), 2);
+    assert_eq!(
+        queries.account_in(ClientId(1), Asset::default()).await?,
+        None
+    );
+
+    Ok(())
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use common::{deposit, withdrawal};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::errors::EngineError;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::rejection::{Origin, Rejection};
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+fn origin(line: u64, row: &str) -> Option<Origin> {
+    Some(Origin {
+        line,
+        row: String::from(row),
+    })
+}
+
+#[tokio::test]
+async fn report_engine_rejection() -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut engine = PaymentEngine::default().with_rejections(sender);
+
+    let deposit = deposit(1, 1, dec!(2));
+
+    engine
+        .process_from(deposit.clone(), origin(2, "deposit,1,1,2"))
+        .await?;
+
+    let confirmation = engine
+        .process_from(deposit, origin(3, "deposit,1,1,2"))
+        .await;
+
+    assert_eq!(
+        confirmation,
+        Err(EngineError::DuplicateTransaction(TransactionId(1)))
+    );
+
+    engine.report().await?;
+
+    assert_eq!(
+        receiver.recv().await,
+        Some(Rejection {
+            line: Some(3),
+            client: Some(ClientId(1)),
+            tx: Some(TransactionId(1)),
+            code: 1005,
+            error: "DuplicateTransaction",
+            message: String::from("Transaction already processed: 1"),
+            row: Some(String::from("deposit,1,1,2")),
+        })
+    );
+    assert_eq!(receiver.recv().await, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn report_worker_rejection() -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut engine = PaymentEngine::default().with_rejections(sender);
+
+    let withdrawal = withdrawal(1, 1, dec!(2));
+
+    let confirmation = engine
+        .process_from(withdrawal, origin(2, "withdrawal,1,1,2"))
+        .await;
+
+    assert!(confirmation.is_ok());
+
+    engine.report().await?;
+
+    assert_eq!(
+        receiver.recv().await,
+        Some(Rejection {
+            line: Some(2),
+            client: Some(ClientId(1)),
+            tx: Some(TransactionId(1)),
+            code: 1004,
+            error: "NotEnoughMany",
+            message: String::from("Not enough funds to process transaction: 1"),
+            row: Some(String::from("withdrawal,1,1,2")),
+        })
+    );
+    assert_eq!(receiver.recv().await, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn report_failed_transfer() -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+    let mut engine = PaymentEngine::default().with_rejections(sender);
+
+    let transfer = Transaction::Transfer {
+        client: ClientId(1),
+        to: ClientId(2),
+        trade: TransactionId(1),
+        amount: dec!(2),
+        asset: Asset::default(),
+    };
+
+    let confirmation = engine.submit(transfer).await?.await;
+
+    assert!(confirmation.is_err());
+
+    engine.report().await?;
+
+    let rejection = receiver.recv().await.unwrap();
+
+    assert_eq!(rejection.line, None);
+    assert_eq!(rejection.code, 1004);
+    assert_eq!(receiver.recv().await, None);
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use indoc::indoc;
+use payment_engine::model::account::{Account, LockReason};
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::report::{Report, ReportOrder};
+use payment_engine::model::trade::TransactionId;
+use payment_engine::output::report::ReportFormat;
+use rust_decimal_macros::dec;
+
+fn report() -> Report {
+    Report::new(vec![
+        Account {
+            client: ClientId(1),
+            asset: Asset::default(),
+            available: dec!(2),
+            held: dec!(0.5),
+            total: dec!(2.5),
+            fees: dec!(0),
+            locked: false,
+            lock_reason: None,
+        },
+        Account {
+            client: ClientId(2),
+            asset: Asset::default(),
+            available: dec!(0.1234),
+            held: dec!(0),
+            total: dec!(0.1234),
+            fees: dec!(0),
+            locked: true,
+            lock_reason: Some(LockReason::Chargeback(TransactionId(7))),
+        },
+    ])
+}
+
+fn render(format: ReportFormat) -> anyhow::Result<String> {
+    let mut output = vec![];
+    format.write(&report(), &mut output)?;
+    Ok(String::from_utf8(output)?)
+}
+
+#[test]
+fn write_json_report() -> anyhow::Result<()> {
+    let expected = concat!(
+        r#"[{"client":1,"available":"2.0000","held":"0.5000","total":"2.5000","locked":false,"lock_reason":null},"#,
+        r#"{"client":2,"available":"0.1234","held":"0.0000","total":"0.1234","locked":true,"lock_reason":"chargeback of transaction 7"}]"#,
+        "\n"
+    );
+
+    assert_eq!(render(ReportFormat::Json)?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn write_ndjson_report() -> anyhow::Result<()> {
+    let expected = indoc! {r#"
+        {"client":1,"available":"2.0000","held":"0.5000","total":"2.5000","locked":false,"lock_reason":null}
+        {"client":2,"available":"0.1234","held":"0.0000","total":"0.1234","locked":true,"lock_reason":"chargeback of transaction 7"}
+    "#};
+
+    assert_eq!(render(ReportFormat::Ndjson)?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn write_csv_report() -> anyhow::Result<()> {
+    let expected = indoc! {r#"
+        client,available,held,total,locked
+        1,2.0000,0.5000,2.5000,false
+        2,0.1234,0.0000,0.1234,true
+    "#};
+
+    assert_eq!(render(ReportFormat::Csv)?, expected);
+
+    Ok(())
+}
+
+#[test]
+fn write_lock_reason_of_operator_lock() -> anyhow::Result<()> {
+    let mut accounts = report().accounts().to_vec();
+    accounts[0].locked = true;
+    accounts[0].lock_reason = Some(LockReason::Admin {
+        operator: "alice".to_string(),
+        reason: "fraud, suspected".to_string(),
+    });
+
+    let expected = indoc! {r#"
+        client,available,held,total,locked,lock_reason
+        1,2.0000,0.5000,2.5000,true,"fraud, suspected (by alice)"
+        2,0.1234,0.0000,0.1234,true,chargeback of transaction 7
+    "#};
+
+    assert_eq!(Report::new(accounts).to_string(), expected);
+
+    Ok(())
+}
+
+#[test]
+fn sort_report_by_client_and_key() {
+    let rows = |report: &Report| {
+        report
+            .accounts()
+            .iter()
+            .map(|account| account.client)
+            .collect::<Vec<_>>()
+    };
+
+    let report = Report::new(vec![
+        Account {
+            client: ClientId(3),
+            asset: Asset::default(),
+            available: dec!(1),
+            held: dec!(0),
+            total: dec!(1),
+            fees: dec!(0),
+            locked: false,
+            lock_reason: None,
+        },
+        Account {
+            client: ClientId(1),
+            asset: Asset::default(),
+            available: dec!(5),
+            held: dec!(0),
+            total: dec!(5),
+            fees: dec!(0),
+            locked: false,
+            lock_reason: None,
+        },
+        Account {
+            client: ClientId(2),
+            asset: Asset::default(),
+            available: dec!(1),
+            held: dec!(0),
+            total: dec!(1),
+            fees: dec!(0),
+            locked: false,
+            lock_reason: None,
+        },
+    ]);
+
+    assert_eq!(rows(&report), vec![ClientId(1), ClientId(2), ClientId(3)]);
+
+    let report = report.sort_by(ReportOrder::Available);
+
+    assert_eq!(rows(&report), vec![ClientId(2), ClientId(3), ClientId(1)]);
+}
@@ -1,6 +1,6 @@
 use payment_engine::core::wallet::AccountWallet;
 use payment_engine::errors::EngineError;
-use payment_engine::model::account::Account;
+use payment_engine::model::asset::Asset;
 use payment_engine::model::client::ClientId;
 use payment_engine::model::trade::TransactionId;
 use rust_decimal_macros::dec;
@@ -10,11 +10,11 @@ fn init_wallet(client_id: ClientId) -> anyhow::Result<AccountWallet> {
 
     let trade = TransactionId(1);
     let amount = dec!(2);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     let trade = TransactionId(2);
     let amount = dec!(5);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     let trade = TransactionId(2);
     wallet.dispute(trade)?;
@@ -22,6 +22,20 @@ fn init_wallet(client_id: ClientId) -> anyhow::Result<AccountWallet> {
     Ok(wallet)
 }
 
+#[test]
+fn resolve_unexist_transaction() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(3);
+
+    let confirmation = wallet.resolve(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::TransactionNotFound(trade)));
+
+    Ok(())
+}
+
 #[test]
 fn resolve_unexist_dispute() -> anyhow::Result<()> {
     let mut wallet = init_wallet(ClientId(1))?;
@@ -31,7 +45,7 @@ fn resolve_unexist_dispute() -> anyhow::Result<()> {
     let confirmation = wallet.resolve(trade);
 
     assert!(confirmation.is_err());
-    assert_eq!(confirmation, Err(EngineError::TransactionNotFound(trade)));
+    assert_eq!(confirmation, Err(EngineError::NotDisputed(trade)));
 
     Ok(())
 }
@@ -46,7 +60,7 @@ fn resolve_positive_amount() -> anyhow::Result<()> {
 
     assert!(confirmation.is_ok());
 
-    let account: Account = wallet.into();
+    let account = wallet.account(&Asset::default()).unwrap();
 
     assert_eq!(account.client, ClientId(1));
     assert_eq!(account.available, dec!(7));
@@ -56,3 +70,44 @@ fn resolve_positive_amount() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn resolve_disputed_withdrawal() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(3);
+    let amount = dec!(1.5);
+
+    wallet.withdrawal(trade, Asset::default(), amount)?;
+    wallet.dispute(trade)?;
+
+    let confirmation = wallet.resolve(trade);
+
+    assert!(confirmation.is_ok());
+
+    let account = wallet.account(&Asset::default()).unwrap();
+
+    assert_eq!(account.client, ClientId(1));
+    assert_eq!(account.available, dec!(0.5));
+    assert_eq!(account.held, dec!(5));
+    assert_eq!(account.total, dec!(5.5));
+    assert!(!account.locked);
+
+    Ok(())
+}
+
+#[test]
+fn resolve_already_resolved_dispute() -> anyhow::Result<()> {
+    let mut wallet = init_wallet(ClientId(1))?;
+
+    let trade = TransactionId(2);
+
+    wallet.resolve(trade)?;
+
+    let confirmation = wallet.resolve(trade);
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::AlreadyResolved(trade)));
+
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use axum::Router;
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::server::http::{ApiState, router};
+use serde_json::{Value, json};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tower::ServiceExt;
+
+fn app() -> anyhow::Result<Router> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let engine = PaymentEngine::new(2).with_rejections(sender);
+
+    Ok(router(ApiState::new(engine, receiver)))
+}
+
+async fn call(app: &Router, request: Request<Body>) -> anyhow::Result<(StatusCode, Value)> {
+    let response = app.clone().oneshot(request).await?;
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await?;
+
+    let value = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body)?
+    };
+
+    Ok((status, value))
+}
+
+async fn post(app: &Router, body: Value) -> anyhow::Result<(StatusCode, Value)> {
+    let request = Request::post("/transactions")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))?;
+
+    call(app, request).await
+}
+
+async fn get(app: &Router, uri: &str) -> anyhow::Result<(StatusCode, Value)> {
+    call(app, Request::get(uri).body(Body::empty())?).await
+}
+
+#[tokio::test]
+async fn submit_single_transaction() -> anyhow::Result<()> {
+    let app = app()?;
+
+    let (status, body) = post(
+        &app,
+        json!({"type": "deposit", "client": 1, "tx": 1, "amount": 1.5}),
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({"status": "accepted", "tx": 1}));
+
+    let (status, body) = get(&app, "/accounts/1").await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body,
+        json!({
+            "client": 1,
+            "available": "1.5000",
+            "held": "0.0000",
+            "total": "1.5000",
+            "locked": false,
+            "lock_reason": null
+        })
+    );
+
+    let (status, _) = get(&app, "/accounts/2").await?;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = post(
+        &app,
+        json!({"type": "withdrawal", "client": 1, "tx": 2, "amount": 2}),
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["status"], "rejected");
+    assert_eq!(body["code"], 1004);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn submit_batch_of_transactions() -> anyhow::Result<()> {
+    let app = app()?;
+
+    let (status, body) = post(
+        &app,
+        json!([
+            {"type": "deposit", "client": 1, "tx": 1, "amount": "2"},
+            {"type": "deposit", "client": 2, "tx": 1, "amount": "1"},
+            {"type": "deposit", "client": 2, "tx": 2},
+            {"type": "withdrawal", "client": 1, "tx": 3, "amount": "5"}
+        ]),
+    )
+    .await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body[0]["status"], "accepted");
+    assert_eq!(body[1]["status"], "rejected");
+    assert_eq!(body[1]["error"], "DuplicateTransaction");
+    assert_eq!(body[1]["line"], 2);
+    assert_eq!(body[2]["status"], "rejected");
+    assert_eq!(body[2]["error"], "MissingAmount");
+    assert_eq!(body[3]["status"], "rejected");
+    assert_eq!(body[3]["error"], "NotEnoughMany");
+
+    let (status, body) = get(&app, "/accounts").await?;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body.as_array().map(Vec::len), Some(1));
+    assert_eq!(body[0]["total"], "2.0000");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fetch_rejection_reason() -> anyhow::Result<()> {
+    let app = app()?;
+
+    post(
+        &app,
+        json!([
+            {"type": "deposit", "client": 1, "tx": 1, "amount": 1},
+            {"type": "withdrawal", "client": 1, "tx": 2, "amount": 5}
+        ]),
+    )
+    .await?;
+
+    let mut rejection = get(&app, "/rejections/2").await?;
+
+    for _ in 0..50 {
+        if rejection.0 == StatusCode::OK {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        rejection = get(&app, "/rejections/2").await?;
+    }
+
+    let (status, body) = rejection;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body[0]["code"], 1004);
+    assert_eq!(body[0]["error"], "NotEnoughMany");
+
+    let (status, _) = get(&app, "/rejections/1").await?;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn keep_rejections_of_latest_transactions() -> anyhow::Result<()> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let engine = PaymentEngine::new(2).with_rejections(sender);
+    let app = router(ApiState::with_capacity(engine, receiver, 1));
+
+    post(
+        &app,
+        json!([
+            {"type": "withdrawal", "client": 1, "tx": 2, "amount": 5},
+            {"type": "withdrawal", "client": 1, "tx": 3, "amount": 5}
+        ]),
+    )
+    .await?;
+
+    let mut rejection = get(&app, "/rejections/3").await?;
+
+    for _ in 0..50 {
+        if rejection.0 == StatusCode::OK {
+            break;
+        }
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        rejection = get(&app, "/rejections/3").await?;
+    }
+
+    assert_eq!(rejection.0, StatusCode::OK);
+
+    let (status, _) = get(&app, "/rejections/2").await?;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    Ok(())
+}
@@ -0,0 +1,163 @@
+mod common;
+
+use common::{deposit, dispute, withdrawal};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::statement::{StatementLine, StepKind, StepStatus};
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use tokio::sync::mpsc;
+
+async fn statement(
+    client: u16,
+    transactions: Vec<Transaction>,
+) -> anyhow::Result<Vec<StatementLine>> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let mut engine = PaymentEngine::new(2).with_statement(ClientId(client), sender);
+
+    for tx in transactions {
+        engine.process(tx).await.ok();
+    }
+
+    engine.report().await?;
+
+    let mut lines = vec![];
+
+    while let Some(line) = receiver.recv().await {
+        lines.push(line);
+    }
+
+    Ok(lines)
+}
+
+#[tokio::test]
+async fn statement_has_running_balances() -> anyhow::Result<()> {
+    let lines = statement(
+        1,
+        vec![
+            deposit(1, 1, dec!(5)),
+            deposit(2, 2, dec!(7)),
+            dispute(1, 1),
+            Transaction::Chargeback {
+                client: ClientId(1),
+                trade: TransactionId(1),
+            },
+        ],
+    )
+    .await?;
+
+    let steps: Vec<_> = lines
+        .iter()
+        .map(|line| {
+            (
+                line.kind,
+                line.amount,
+                line.available,
+                line.held,
+                line.total,
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        steps,
+        vec![
+            (StepKind::Deposit, Some(dec!(5)), dec!(5), dec!(0), dec!(5)),
+            (StepKind::Dispute, Some(dec!(5)), dec!(0), dec!(5), dec!(5)),
+            (
+                StepKind::Chargeback,
+                Some(dec!(5)),
+                dec!(0),
+                dec!(0),
+                dec!(0)
+            ),
+        ]
+    );
+    assert!(lines.iter().all(|line| line.status == StepStatus::Applied));
+    assert!(lines[2].locked);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn statement_keeps_rejections_in_order() -> anyhow::Result<()> {
+    let lines = statement(
+        1,
+        vec![
+            deposit(1, 1, dec!(5)),
+            deposit(1, 1, dec!(5)),
+            withdrawal(1, 2, dec!(6)),
+            deposit(1, 3, dec!(1)),
+        ],
+    )
+    .await?;
+
+    let steps: Vec<_> = lines
+        .iter()
+        .map(|line| (line.tx, line.status, line.error.as_deref(), line.total))
+        .collect();
+
+    assert_eq!(
+        steps,
+        vec![
+            (Some(TransactionId(1)), StepStatus::Applied, None, dec!(5)),
+            (
+                Some(TransactionId(1)),
+                StepStatus::Rejected,
+                Some("Transaction already processed: 1"),
+                dec!(5)
+            ),
+            (
+                Some(TransactionId(2)),
+                StepStatus::Rejected,
+                Some("Not enough funds to process transaction: 2"),
+                dec!(5)
+            ),
+            (Some(TransactionId(3)), StepStatus::Applied, None, dec!(6)),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn statement_shows_transfers_of_both_sides() -> anyhow::Result<()> {
+    let transfer = |trade: u32, amount: Decimal| Transaction::Transfer {
+        client: ClientId(1),
+        to: ClientId(2),
+        trade: TransactionId(trade),
+        amount,
+        asset: Asset::default(),
+    };
+
+    let transactions = vec![
+        deposit(1, 1, dec!(5)),
+        transfer(2, dec!(2)),
+        transfer(3, dec!(4)),
+    ];
+
+    let sender = statement(1, transactions.clone()).await?;
+    let recipient = statement(2, transactions).await?;
+
+    let steps: Vec<_> = sender
+        .iter()
+        .map(|line| (line.kind, line.status, line.total))
+        .collect();
+
+    assert_eq!(
+        steps,
+        vec![
+            (StepKind::Deposit, StepStatus::Applied, dec!(5)),
+            (StepKind::Transfer, StepStatus::Applied, dec!(3)),
+            (StepKind::Transfer, StepStatus::Rejected, dec!(3)),
+        ]
+    );
+    assert_eq!(recipient.len(), 1);
+    assert_eq!(recipient[0].kind, StepKind::Credit);
+    assert_eq!(recipient[0].total, dec!(2));
+
+    Ok(())
+}
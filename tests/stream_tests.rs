@@ -0,0 +1,85 @@
+mod common;
+
+use common::withdrawal;
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::errors::EngineError;
+use payment_engine::input::blocking::{self, ReadRecord};
+use payment_engine::input::csv::CsvReader;
+use rust_decimal_macros::dec;
+use std::io::Cursor;
+use tokio_stream::StreamExt;
+
+const CONTENT: &str = "type,client,tx,amount\ndeposit,1,1,1.0\nunknown,1,2,\nwithdrawal,1,3,0.5\n";
+
+#[tokio::test]
+async fn stream_transactions_from_csv_reader() -> anyhow::Result<()> {
+    let reader = CsvReader::from_reader(Cursor::new(CONTENT))?;
+
+    let records: Vec<ReadRecord> = blocking::from_reader(reader, 1).collect().await;
+
+    assert_eq!(records.len(), 3);
+    assert!(records[0].transaction.is_ok());
+    assert!(records[1].transaction.is_err());
+    assert_eq!(
+        records[1].origin.as_ref().map(|origin| origin.line),
+        Some(3)
+    );
+    assert!(records[2].transaction.is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn compose_transactions_stream() -> anyhow::Result<()> {
+    let reader = CsvReader::from_reader(Cursor::new(CONTENT))?;
+
+    let mut transactions = blocking::from_reader(reader, 1)
+        .filter_map(|record| record.transaction.ok())
+        .skip(1);
+
+    assert_eq!(transactions.next().await, Some(withdrawal(1, 3, dec!(0.5))));
+    assert_eq!(transactions.next().await, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn feed_engine_from_stream() -> anyhow::Result<()> {
+    let reader = CsvReader::from_reader(Cursor::new(CONTENT))?;
+
+    let mut engine = PaymentEngine::default();
+    let mut transactions = blocking::from_reader(reader, 1);
+
+    while let Some(record) = transactions.next().await {
+        if let Ok(transaction) = record.transaction {
+            engine.process_from(transaction, record.origin).await?;
+        }
+    }
+
+    let report = engine.report().await?;
+
+    assert_eq!(
+        report.to_string(),
+        "client,available,held,total,locked\n1,0.5000,0.0000,0.5000,false\n"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn open_reader_on_blocking_thread() -> anyhow::Result<()> {
+    let open = || CsvReader::from_reader(Cursor::new(CONTENT));
+
+    let records: Vec<ReadRecord> = blocking::open(open, 1).await?.collect().await;
+
+    assert_eq!(records.len(), 3);
+
+    let open = || CsvReader::new("missing.csv");
+
+    assert!(matches!(
+        blocking::open(open, 1).await.err(),
+        Some(EngineError::FileNotFound(_))
+    ));
+
+    Ok(())
+}
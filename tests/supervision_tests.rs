@@ -0,0 +1,226 @@
+mod common;
+
+use common::{deposit, withdrawal};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::fees::{FeeKind, FeePolicy};
+use payment_engine::core::supervisor::Supervision;
+use payment_engine::errors::EngineError;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::TransactionId;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+/// Amount which makes the worker handling it panic.
+const FAULT: Decimal = dec!(13);
+
+struct FaultyFees;
+
+impl FeePolicy for FaultyFees {
+    fn fee(&self, _: FeeKind, amount: Decimal) -> Decimal {
+        if amount == FAULT {
+            panic!("Injected worker fault");
+        }
+
+        Decimal::ZERO
+    }
+}
+
+fn engine(supervision: Supervision) -> anyhow::Result<PaymentEngine> {
+    Ok(PaymentEngine::new(2)
+        .with_fees(Arc::new(FaultyFees), ClientId(0))
+        .with_supervision(supervision))
+}
+
+/// Third deposit makes the worker of client 1 panic.
+async fn poison(engine: &mut PaymentEngine) -> anyhow::Result<()> {
+    engine.process(deposit(1, 1, dec!(10))).await?;
+    engine.process(deposit(2, 2, dec!(5))).await?;
+    engine.process(deposit(1, 3, FAULT)).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn isolate_failed_worker() -> anyhow::Result<()> {
+    let mut engine = engine(Supervision::Isolate)?;
+
+    engine.process(deposit(2, 2, dec!(5))).await?;
+
+    let ack = engine.submit(deposit(1, 1, FAULT)).await?;
+
+    assert_eq!(ack.await, Err(EngineError::WorkerFailed(1, 2)));
+
+    let ack = engine.submit(deposit(1, 3, dec!(1))).await?;
+
+    assert_eq!(ack.await, Err(EngineError::WorkerFailed(1, 2)));
+
+    engine.process(deposit(2, 4, dec!(1))).await?;
+
+    let account = engine.account(ClientId(2)).await?.unwrap();
+
+    assert_eq!(account.total, dec!(6));
+
+    assert_eq!(
+        engine.report().await.err(),
+        Some(EngineError::WorkerFailed(1, 2))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_transactions_lost_with_isolated_worker() -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let mut engine = engine(Supervision::Isolate)?.with_rejections(sender);
+
+    poison(&mut engine).await?;
+
+    // Queued behind the failing deposit, or refused once the worker is gone.
+    let _ = engine.process(deposit(1, 5, dec!(1))).await;
+
+    assert_eq!(
+        engine.report().await.err(),
+        Some(EngineError::WorkerFailed(1, 2))
+    );
+
+    let mut rejected = vec![];
+
+    while let Some(rejection) = receiver.recv().await {
+        assert_eq!(rejection.error, "WorkerFailed");
+        rejected.extend(rejection.tx.map(|tx| tx.0));
+    }
+
+    rejected.sort();
+
+    assert_eq!(rejected, vec![3, 5]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restart_failed_worker_on_report() -> anyhow::Result<()> {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let mut engine = engine(Supervision::Restart)?.with_rejections(sender);
+
+    poison(&mut engine).await?;
+
+    let report = engine.report().await?;
+
+    let totals: Vec<_> = report
+        .accounts()
+        .iter()
+        .map(|account| (account.client, account.total))
+        .collect();
+
+    assert_eq!(
+        totals,
+        vec![(ClientId(1), dec!(10)), (ClientId(2), dec!(5))]
+    );
+
+    let rejection = receiver.recv().await.unwrap();
+
+    assert_eq!(rejection.tx, Some(TransactionId(3)));
+    assert_eq!(rejection.error, "WorkerFailed");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restart_failed_worker_on_request() -> anyhow::Result<()> {
+    let mut engine = engine(Supervision::Restart)?;
+
+    engine.process(deposit(1, 1, dec!(10))).await?;
+
+    let ack = engine.submit(deposit(1, 2, FAULT)).await?;
+
+    assert_eq!(ack.await, Err(EngineError::WorkerFailed(1, 2)));
+
+    let withdrawal = withdrawal(1, 3, dec!(1));
+
+    assert_eq!(engine.submit(withdrawal).await?.await, Ok(()));
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.total, dec!(9));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restart_from_checkpoint() -> anyhow::Result<()> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("state.json");
+    let path = path.to_str().unwrap();
+
+    let mut engine = engine(Supervision::Restart)?;
+
+    poison(&mut engine).await?;
+
+    engine.checkpoint(path).await?;
+
+    engine.process(deposit(2, 4, dec!(1))).await?;
+    engine.process(deposit(1, 5, dec!(1))).await?;
+
+    let report = engine.report().await?;
+
+    let totals: Vec<_> = report
+        .accounts()
+        .iter()
+        .map(|account| (account.client, account.total))
+        .collect();
+
+    assert_eq!(
+        totals,
+        vec![(ClientId(1), dec!(11)), (ClientId(2), dec!(6))]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn restart_from_compacted_journal() -> anyhow::Result<()> {
+    let mut engine = engine(Supervision::Restart)?;
+
+    for trade in 1..=10_050 {
+        engine.process(deposit(1, trade, dec!(1))).await?;
+    }
+
+    let ack = engine.submit(deposit(1, 20_000, FAULT)).await?;
+
+    assert_eq!(ack.await, Err(EngineError::WorkerFailed(1, 2)));
+
+    let ack = engine.submit(deposit(1, 20_001, dec!(1))).await?;
+
+    assert_eq!(ack.await, Ok(()));
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.total, dec!(10051));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_overflowing_deposit() -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine.process(deposit(1, 1, Decimal::MAX)).await?;
+
+    let ack = engine.submit(deposit(1, 2, dec!(1))).await?;
+
+    assert_eq!(
+        ack.await,
+        Err(EngineError::BalanceOverflow(TransactionId(2)))
+    );
+
+    let account = engine.account(ClientId(1)).await?.unwrap();
+
+    assert_eq!(account.total, Decimal::MAX);
+
+    Ok(())
+}
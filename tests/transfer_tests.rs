@@ -0,0 +1,162 @@
+mod common;
+
+use common::{deposit, dispute, report_rows, withdrawal};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::errors::EngineError;
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Transaction, TransactionId};
+use rust_decimal_macros::dec;
+
+async fn init_engine() -> anyhow::Result<PaymentEngine> {
+    let mut engine = PaymentEngine::new(2);
+
+    engine.process(deposit(1, 1, dec!(10))).await?;
+
+    engine.process(deposit(2, 2, dec!(1))).await?;
+
+    Ok(engine)
+}
+
+#[tokio::test]
+async fn transfer_between_workers() -> anyhow::Result<()> {
+    let mut engine = init_engine().await?;
+
+    let confirmation = engine
+        .process(Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(2),
+            trade: TransactionId(3),
+            amount: dec!(4),
+            asset: Asset::default(),
+        })
+        .await;
+
+    assert!(confirmation.is_ok());
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,6.0000,0.0000,6.0000,false",
+            "2,5.0000,0.0000,5.0000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transfer_more_than_account_has() -> anyhow::Result<()> {
+    let mut engine = init_engine().await?;
+
+    let trade = TransactionId(3);
+
+    let confirmation = engine
+        .submit(Transaction::Transfer {
+            client: ClientId(2),
+            to: ClientId(1),
+            trade,
+            amount: dec!(4),
+            asset: Asset::default(),
+        })
+        .await?
+        .await;
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::NotEnoughMany(trade)));
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,10.0000,0.0000,10.0000,false",
+            "2,1.0000,0.0000,1.0000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transfer_to_frozen_account() -> anyhow::Result<()> {
+    let mut engine = init_engine().await?;
+
+    engine.process(dispute(2, 2)).await?;
+
+    engine
+        .process(Transaction::Chargeback {
+            client: ClientId(2),
+            trade: TransactionId(2),
+        })
+        .await?;
+
+    let confirmation = engine
+        .submit(Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(2),
+            trade: TransactionId(3),
+            amount: dec!(4),
+            asset: Asset::default(),
+        })
+        .await?
+        .await;
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::FrozenAccount(ClientId(2))));
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,10.0000,0.0000,10.0000,false",
+            "2,0.0000,0.0000,0.0000,true"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn transfer_to_same_account() -> anyhow::Result<()> {
+    let mut engine = init_engine().await?;
+
+    let trade = TransactionId(3);
+
+    let confirmation = engine
+        .process(Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(1),
+            trade,
+            amount: dec!(4),
+            asset: Asset::default(),
+        })
+        .await;
+
+    assert!(confirmation.is_err());
+    assert_eq!(confirmation, Err(EngineError::InvalidTransfer(trade)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn withdraw_transferred_funds() -> anyhow::Result<()> {
+    let mut engine = init_engine().await?;
+
+    engine
+        .process(Transaction::Transfer {
+            client: ClientId(1),
+            to: ClientId(2),
+            trade: TransactionId(3),
+            amount: dec!(4),
+            asset: Asset::default(),
+        })
+        .await?;
+
+    // Queued after the credit of the recipient, even though `process` does not wait for it.
+    engine.process(withdrawal(2, 4, dec!(5))).await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,6.0000,0.0000,6.0000,false",
+            "2,0.0000,0.0000,0.0000,false"
+        ]
+    );
+
+    Ok(())
+}
@@ -0,0 +1,323 @@
+mod common;
+
+use common::{deposit, dispute, report_rows};
+use payment_engine::core::engine::PaymentEngine;
+use payment_engine::core::policy::WalletPolicy;
+use payment_engine::core::snapshot::EngineSnapshot;
+use payment_engine::core::wal::{LogEntry, WalSync};
+use payment_engine::errors::EngineError;
+use payment_engine::model::admin::{AdminAction, AdminCommand};
+use payment_engine::model::asset::Asset;
+use payment_engine::model::client::ClientId;
+use payment_engine::model::trade::{Timestamp, Transaction, TransactionId};
+use rust_decimal_macros::dec;
+use serde_json::json;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use tempfile::TempDir;
+use tokio::sync::mpsc;
+
+async fn init_wal(path: &str) -> anyhow::Result<()> {
+    let mut engine = PaymentEngine::new(2).recover(path).await?;
+
+    for (client, trade, amount) in [(1, 1, dec!(2)), (2, 2, dec!(5))] {
+        engine.process(deposit(client, trade, amount)).await?;
+    }
+
+    engine.process(dispute(2, 2)).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recover_state_from_wal() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    init_wal(path).await?;
+
+    let engine = PaymentEngine::new(3).recover(path).await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,2.0000,0.0000,2.0000,false",
+            "2,0.0000,5.0000,5.0000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reject_duplicates_after_recovery() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    init_wal(path).await?;
+
+    let mut engine = PaymentEngine::default().recover(path).await?;
+
+    let trade = TransactionId(1);
+
+    let confirmation = engine.process(deposit(1, trade.0, dec!(2))).await;
+
+    assert_eq!(confirmation, Err(EngineError::DuplicateTransaction(trade)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn truncate_torn_wal_tail() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    init_wal(path).await?;
+
+    let length = fs::metadata(path)?.len();
+
+    let mut file = OpenOptions::new().append(true).open(path)?;
+    file.write_all(&[64, 0, 0, 0, 1, 2, 3, 4, b'{'])?;
+
+    let engine = PaymentEngine::default().recover(path).await?;
+
+    assert_eq!(fs::metadata(path)?.len(), length);
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,2.0000,0.0000,2.0000,false",
+            "2,0.0000,5.0000,5.0000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn drop_wal_record_with_invalid_checksum() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    init_wal(path).await?;
+
+    let mut content = fs::read(path)?;
+    let last = content.len() - 2;
+    content[last] ^= 0xFF;
+    fs::write(path, content)?;
+
+    let engine = PaymentEngine::default().recover(path).await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,2.0000,0.0000,2.0000,false",
+            "2,5.0000,0.0000,5.0000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn checkpoint_clears_wal() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let wal = directory.path().join("engine.wal");
+    let wal = wal.to_str().unwrap();
+    let snapshot = directory.path().join("state.json");
+    let snapshot = snapshot.to_str().unwrap();
+
+    init_wal(wal).await?;
+
+    let mut engine = PaymentEngine::default().recover(wal).await?;
+    engine.checkpoint(snapshot).await?;
+
+    assert_eq!(fs::metadata(wal)?.len(), 0);
+
+    engine
+        .process(Transaction::Resolve {
+            client: ClientId(2),
+            trade: TransactionId(2),
+        })
+        .await?;
+
+    let engine = PaymentEngine::default()
+        .restore(EngineSnapshot::load(snapshot)?)?
+        .recover(wal)
+        .await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec![
+            "1,2.0000,0.0000,2.0000,false",
+            "2,5.0000,0.0000,5.0000,false"
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn skip_wal_entries_saved_in_snapshot() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let wal = directory.path().join("engine.wal");
+    let wal = wal.to_str().unwrap();
+    let snapshot = directory.path().join("state.json");
+    let snapshot = snapshot.to_str().unwrap();
+
+    let mut engine = PaymentEngine::default().recover(wal).await?;
+
+    for action in [AdminAction::Lock, AdminAction::Unlock] {
+        engine
+            .admin(AdminCommand {
+                action,
+                client: ClientId(1),
+                operator: String::from("ops"),
+                reason: String::from("review"),
+            })
+            .await?;
+    }
+
+    // Crash after the snapshot is saved but before the log is cleared.
+    let log = fs::read(wal)?;
+    engine.checkpoint(snapshot).await?;
+    fs::write(wal, log)?;
+
+    let engine = PaymentEngine::default()
+        .restore(EngineSnapshot::load(snapshot)?)?
+        .recover(wal)
+        .await?;
+
+    assert_eq!(engine.audit().len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replayed_rejections_are_not_reported() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    let mut engine = PaymentEngine::new(2).recover(path).await?;
+
+    engine.process(dispute(1, 7)).await?;
+
+    engine.report().await?;
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let mut engine = PaymentEngine::new(2)
+        .with_rejections(sender)
+        .recover(path)
+        .await?;
+
+    assert!(receiver.try_recv().is_err());
+
+    engine.process(dispute(1, 8)).await?;
+
+    engine.report().await?;
+
+    let rejection = receiver.recv().await.unwrap();
+
+    assert_eq!(rejection.tx, Some(TransactionId(8)));
+    assert!(receiver.recv().await.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fail_recovery_on_invalid_entry() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    let policy = WalletPolicy {
+        precision: 8,
+        ..WalletPolicy::default()
+    };
+
+    let mut engine = PaymentEngine::new(2)
+        .with_policy(policy)
+        .recover(path)
+        .await?;
+
+    engine.process(deposit(1, 1, dec!(1.00000001))).await?;
+
+    engine.report().await?;
+
+    let recovery = PaymentEngine::new(2).recover(path).await;
+
+    assert_eq!(
+        recovery.err(),
+        Some(EngineError::InvalidPrecision(TransactionId(1)))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recover_wal_synced_in_batches() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    let mut engine = PaymentEngine::new(2)
+        .with_wal_sync(WalSync::Batch(2))
+        .recover(path)
+        .await?;
+
+    for trade in 1..=3 {
+        engine.process(deposit(1, trade, dec!(1))).await?;
+    }
+
+    drop(engine);
+
+    let engine = PaymentEngine::new(2)
+        .with_wal_sync(WalSync::Never)
+        .recover(path)
+        .await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec!["1,3.0000,0.0000,3.0000,false"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_does_not_check_the_clock() -> anyhow::Result<()> {
+    let directory = TempDir::new()?;
+    let path = directory.path().join("engine.wal");
+    let path = path.to_str().unwrap();
+
+    // Logged by an engine whose clock was far ahead of the current one.
+    let entry = LogEntry::Transaction(Transaction::Deposit {
+        client: ClientId(1),
+        trade: TransactionId(1),
+        amount: dec!(2),
+        asset: Asset::default(),
+        timestamp: Some(Timestamp(u64::MAX)),
+    });
+
+    let payload = serde_json::to_vec(&json!({ "sequence": 1, "entry": entry }))?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(&(payload.len() as u32).to_le_bytes())?;
+    file.write_all(&crc32fast::hash(&payload).to_le_bytes())?;
+    file.write_all(&payload)?;
+    drop(file);
+
+    let engine = PaymentEngine::new(2).recover(path).await?;
+
+    assert_eq!(
+        report_rows(engine).await?,
+        vec!["1,2.0000,0.0000,2.0000,false"]
+    );
+
+    Ok(())
+}
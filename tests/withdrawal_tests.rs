@@ -1,6 +1,6 @@
 use payment_engine::core::wallet::AccountWallet;
 use payment_engine::errors::EngineError;
-use payment_engine::model::account::Account;
+use payment_engine::model::asset::Asset;
 use payment_engine::model::client::ClientId;
 use payment_engine::model::trade::TransactionId;
 use rust_decimal_macros::dec;
@@ -10,11 +10,11 @@ fn init_wallet(client_id: ClientId) -> anyhow::Result<AccountWallet> {
 
     let trade = TransactionId(1);
     let amount = dec!(2);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     let trade = TransactionId(2);
     let amount = dec!(5);
-    wallet.deposit(trade, amount)?;
+    wallet.deposit(trade, Asset::default(), amount)?;
 
     Ok(wallet)
 }
@@ -26,7 +26,7 @@ fn withdrawal_negative_amount() -> anyhow::Result<()> {
     let trade = TransactionId(3);
     let amount = dec!(-1.5);
 
-    let confirmation = wallet.withdrawal(trade, amount);
+    let confirmation = wallet.withdrawal(trade, Asset::default(), amount);
 
     assert!(confirmation.is_err());
     assert_eq!(confirmation, Err(EngineError::NegativeAmount(trade)));
@@ -41,7 +41,7 @@ fn withdrawal_zero_amount() -> anyhow::Result<()> {
     let trade = TransactionId(3);
     let amount = dec!(0.0);
 
-    let confirmation = wallet.withdrawal(trade, amount);
+    let confirmation = wallet.withdrawal(trade, Asset::default(), amount);
 
     assert!(confirmation.is_err());
     assert_eq!(confirmation, Err(EngineError::NegativeAmount(trade)));
@@ -56,7 +56,7 @@ fn withdrawal_more_than_account_has() -> anyhow::Result<()> {
     let trade = TransactionId(3);
     let amount = dec!(10);
 
-    let confirmation = wallet.withdrawal(trade, amount);
+    let confirmation = wallet.withdrawal(trade, Asset::default(), amount);
 
     assert!(confirmation.is_err());
     assert_eq!(confirmation, Err(EngineError::NotEnoughMany(trade)));
@@ -71,18 +71,18 @@ fn withdrawal_positive_amount() -> anyhow::Result<()> {
     let trade = TransactionId(3);
     let amount = dec!(1.5);
 
-    let confirmation = wallet.withdrawal(trade, amount);
+    let confirmation = wallet.withdrawal(trade, Asset::default(), amount);
 
     assert!(confirmation.is_ok());
 
     let trade = TransactionId(4);
     let amount = dec!(2.0);
 
-    let confirmation = wallet.withdrawal(trade, amount);
+    let confirmation = wallet.withdrawal(trade, Asset::default(), amount);
 
     assert!(confirmation.is_ok());
 
-    let account: Account = wallet.into();
+    let account = wallet.account(&Asset::default()).unwrap();
 
     assert_eq!(account.client, ClientId(1));
     assert_eq!(account.available, dec!(3.5));